@@ -0,0 +1,371 @@
+//➔ Garbage attacks between the boards of a versus game.
+//
+// Every clear sends garbage lines to the opponent, as many as the attack rules
+// give for it: by the lines cleared and whether it was a T-spin, plus a bonus
+// for such a clear right after another one (back to back), for clears in a row
+// (combo) and for clears that empty the board. Garbage sent to a board waits in
+// its garbage meter, where what that board sends back cancels it first if the
+// rules say so. Once its delay is over, it rises into the board as soon as a
+// piece locks without clearing a line, up to the cap of a piece, every attack
+// with a hole of its own.
+//
+// The rules are read from the attack file, one `key value...` entry per line,
+// so the rules of other games can be played by:
+//
+//   clear 0 0 1 2 4          lines sent for no line, a single ... a tetris
+//   tspin 0 2 4 6            lines sent for a T-spin of no line ... a triple
+//   combo 0 0 1 1 2 2 3      lines added for the clears in a row before, the last one for longer combos
+//   back_to_back 1           lines added to a tetris or T-spin right after another one
+//   all_clear 10             lines added to a clear that empties the board
+//   garbage_delay 0          milliseconds garbage waits in the meter before it can rise
+//   cancel on                whether sending lines cancels the garbage in the meter
+//   messiness 0              chance in percent (0 to 100) for the hole to move between two rows of an attack
+//   garbage_cap 0            garbage rows that rise after a piece, 0 for no cap
+
+use crate::engine::LockResult;
+use crate::garbage::GarbageGenerator;
+use crate::save::{invalid, parse_value};
+use crate::{read_from_file, write_into_file, Tetris};
+
+use std::collections::VecDeque;
+use std::io;
+use std::time::Duration;
+
+pub const ATTACK_FILE: &str = "attack.txt";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttackRules {
+    //➔ lines sent for clearing 0 to 4 lines
+    pub clear: [u32; 5],
+    //➔ lines sent for T-spins clearing 0 to 3 lines
+    pub t_spin: [u32; 4],
+    //➔ lines added for the clears in a row before this one, the last entry for any longer combo
+    pub combo: Vec<u32>,
+    pub back_to_back: u32,
+    pub all_clear: u32,
+    pub garbage_delay: Duration,
+    pub cancel: bool,
+    pub messiness: u32,
+    //➔ garbage rows rising after a piece at most, 0 for no cap
+    pub garbage_cap: u32,
+}
+
+impl Default for AttackRules {
+    fn default() -> AttackRules {
+        AttackRules {
+            clear: [0, 0, 1, 2, 4],
+            t_spin: [0, 2, 4, 6],
+            combo: vec![0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 4, 5],
+            back_to_back: 1,
+            all_clear: 10,
+            garbage_delay: Duration::ZERO,
+            cancel: true,
+            messiness: 0,
+            garbage_cap: 0,
+        }
+    }
+}
+
+//➔ The numbers of an entry, exactly `count` of them unless `count` is 0
+fn parse_numbers(key: &str, parts: std::str::SplitWhitespace, count: usize) -> io::Result<Vec<u32>> {
+    let numbers = parts.map(|part| parse_value(key, Some(part))).collect::<io::Result<Vec<u32>>>()?;
+    if numbers.is_empty() || (count > 0 && numbers.len() != count) {
+        return Err(invalid(format!("`{}` needs {} numbers, found {}", key, count.max(1), numbers.len())));
+    }
+    Ok(numbers)
+}
+
+impl AttackRules {
+    //➔ Reads the attack file, writing the default rules if there is none yet.
+    //   A file that cannot be read is reported and the default rules are played by
+    pub fn load() -> AttackRules {
+        let content = match read_from_file(ATTACK_FILE) {
+            Ok(content) => content,
+            Err(_) => {
+                let rules = AttackRules::default();
+                if let Err(e) = rules.save() {
+                    println!("Could not write {}: {}", ATTACK_FILE, e);
+                }
+                return rules;
+            },
+        };
+        match AttackRules::parse(&content) {
+            Ok(rules) => rules,
+            Err(e) => {
+                println!("Could not read the attack rules from {}, the default ones are used: {}", ATTACK_FILE, e);
+                AttackRules::default()
+            },
+        }
+    }
+
+    //➔ Entries left out keep their default
+    pub fn parse(content: &str) -> io::Result<AttackRules> {
+        let mut rules = AttackRules::default();
+        for line in content.lines() {
+            let mut parts = line.split_whitespace();
+            let key = match parts.next() {
+                Some(key) if !key.starts_with('#') => key,
+                _ => continue,
+            };
+            match key {
+                "clear" => rules.clear.copy_from_slice(&parse_numbers(key, parts, 5)?),
+                "tspin" => rules.t_spin.copy_from_slice(&parse_numbers(key, parts, 4)?),
+                "combo" => rules.combo = parse_numbers(key, parts, 0)?,
+                "back_to_back" => rules.back_to_back = parse_value(key, parts.next())?,
+                "all_clear" => rules.all_clear = parse_value(key, parts.next())?,
+                "garbage_delay" => rules.garbage_delay = Duration::from_millis(parse_value(key, parts.next())?),
+                "cancel" => {
+                    let cancel: String = parse_value(key, parts.next())?;
+                    rules.cancel = match cancel.as_str() {
+                        "on" => true,
+                        "off" => false,
+                        _ => return Err(invalid(format!("`cancel` must be `on` or `off`, got `{}`", cancel))),
+                    };
+                },
+                "messiness" => {
+                    rules.messiness = parse_value(key, parts.next())?;
+                    if rules.messiness > 100 {
+                        return Err(invalid(format!("`messiness` is a percentage, got `{}`", rules.messiness)));
+                    }
+                },
+                "garbage_cap" => rules.garbage_cap = parse_value(key, parts.next())?,
+                _ => return Err(invalid(format!("unknown entry `{}`", key))),
+            }
+        }
+        Ok(rules)
+    }
+
+    //➔ The rules as the attack file writes them, read back by `parse`
+    pub fn text(&self) -> String {
+        let numbers = |numbers: &[u32]| numbers.iter().map(|number| number.to_string()).collect::<Vec<_>>().join(" ");
+        let mut content = String::new();
+        content.push_str(&format!("clear {}\n", numbers(&self.clear)));
+        content.push_str(&format!("tspin {}\n", numbers(&self.t_spin)));
+        content.push_str(&format!("combo {}\n", numbers(&self.combo)));
+        content.push_str(&format!("back_to_back {}\n", self.back_to_back));
+        content.push_str(&format!("all_clear {}\n", self.all_clear));
+        content.push_str(&format!("garbage_delay {}\n", self.garbage_delay.as_millis()));
+        content.push_str(&format!("cancel {}\n", if self.cancel { "on" } else { "off" }));
+        content.push_str(&format!("messiness {}\n", self.messiness));
+        content.push_str(&format!("garbage_cap {}\n", self.garbage_cap));
+        content
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        write_into_file(&self.text(), ATTACK_FILE)
+    }
+}
+
+//➔ Garbage in the meter: its lines and the engine time it can rise from
+struct Incoming {
+    lines: u32,
+    ready_at: Duration,
+}
+
+pub struct Attack {
+    rules: AttackRules,
+    //➔ clears in a row so far, 0 after a piece that cleared nothing
+    combo: u32,
+    //➔ the last clear was a tetris or a T-spin
+    back_to_back: bool,
+    //➔ attacks received and not in the board yet, the oldest first: the garbage meter
+    incoming: VecDeque<Incoming>,
+    //➔ lines to send to the opponent, until the game loop takes them
+    outgoing: u32,
+    //➔ lines sent over the whole game, after cancelling
+    pub lines_sent: u32,
+    generator: GarbageGenerator,
+}
+
+impl Attack {
+    //➔ The holes of the garbage rising into the board are drawn from `seed`
+    pub fn new(rules: AttackRules, seed: u64) -> Attack {
+        let generator = GarbageGenerator::new(seed, rules.messiness);
+        Attack {
+            rules,
+            combo: 0,
+            back_to_back: false,
+            incoming: VecDeque::new(),
+            outgoing: 0,
+            lines_sent: 0,
+            generator,
+        }
+    }
+
+    //➔ Garbage lines in the meter
+    pub fn pending(&self) -> u32 {
+        self.incoming.iter().map(|incoming| incoming.lines).sum()
+    }
+
+    //➔ Puts `lines` of garbage sent at engine time `now` into the meter
+    pub fn receive(&mut self, lines: u32, now: Duration) {
+        if lines > 0 {
+            self.incoming.push_back(Incoming { lines, ready_at: now + self.rules.garbage_delay });
+        }
+    }
+
+    //➔ Takes the lines to send to the opponent
+    pub fn send(&mut self) -> u32 {
+        std::mem::take(&mut self.outgoing)
+    }
+
+    //➔ Lines a piece that locked with `lock` attacks with, keeping track of combos and back to backs
+    fn attack(&mut self, lock: LockResult) -> u32 {
+        if lock.lines == 0 {
+            self.combo = 0;
+            return 0;
+        }
+        let rules = &self.rules;
+        let lines = lock.lines.min(4) as usize;
+        let difficult = lock.lines >= 4 || lock.t_spin;
+        let mut attack = if lock.t_spin { rules.t_spin[lines.min(3)] } else { rules.clear[lines] };
+        if difficult && self.back_to_back {
+            attack += rules.back_to_back;
+        }
+        self.back_to_back = difficult;
+        if let Some(&last) = rules.combo.last() {
+            attack += rules.combo.get(self.combo as usize).copied().unwrap_or(last);
+        }
+        self.combo += 1;
+        if lock.perfect_clear {
+            attack += rules.all_clear;
+        }
+        attack
+    }
+
+    //➔ To be called once a piece locked at engine time `now`: sends what is left of its attack
+    //   after cancelling the garbage in the meter, or raises the garbage that is ready if it
+    //   cleared nothing. Returns false if the garbage pushed blocks out at the top
+    pub fn locked(&mut self, lock: LockResult, tetris: &mut Tetris, now: Duration) -> bool {
+        let mut attack = self.attack(lock);
+        while attack > 0 && self.rules.cancel {
+            let waiting = match self.incoming.front_mut() {
+                Some(waiting) => waiting,
+                None => break,
+            };
+            let cancelled = attack.min(waiting.lines);
+            waiting.lines -= cancelled;
+            attack -= cancelled;
+            if waiting.lines == 0 {
+                self.incoming.pop_front();
+            }
+        }
+        self.outgoing += attack;
+        self.lines_sent += attack;
+        if lock.lines > 0 {
+            return true;
+        }
+        let width = tetris.width;
+        let mut rows_left = if self.rules.garbage_cap == 0 { u32::MAX } else { self.rules.garbage_cap };
+        while rows_left > 0 {
+            let waiting = match self.incoming.front_mut() {
+                Some(waiting) if waiting.ready_at <= now => waiting,
+                _ => break,
+            };
+            let rows = waiting.lines.min(rows_left);
+            for _ in 0..rows {
+                if !tetris.add_garbage(self.generator.next_row(width)) {
+                    return false;
+                }
+            }
+            waiting.lines -= rows;
+            rows_left -= rows;
+            //➔ an attack cut by the cap keeps its hole for the rest of its rows
+            if waiting.lines == 0 {
+                self.incoming.pop_front();
+                self.generator.new_hole();
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clear(lines: u32) -> LockResult {
+        LockResult { lines, t_spin: false, perfect_clear: false }
+    }
+
+    fn garbage_rows(tetris: &Tetris) -> usize {
+        tetris.game_map.iter().filter(|row| row.iter().any(|&block| block != 0)).count()
+    }
+
+    #[test]
+    fn rules_load_back_as_saved() {
+        let rules = AttackRules {
+            clear: [0, 1, 2, 3, 5],
+            t_spin: [1, 3, 5, 7],
+            combo: vec![0, 1, 2],
+            back_to_back: 2,
+            all_clear: 6,
+            garbage_delay: Duration::from_millis(500),
+            cancel: false,
+            messiness: 30,
+            garbage_cap: 8,
+        };
+        assert_eq!(AttackRules::parse(&rules.text()).expect("saved rules should load"), rules);
+    }
+
+    #[test]
+    fn entries_left_out_keep_their_default() {
+        let rules = AttackRules::parse("# only the delay\ngarbage_delay 250\n").expect("rules should load");
+        assert_eq!(rules, AttackRules { garbage_delay: Duration::from_millis(250), ..AttackRules::default() });
+    }
+
+    #[test]
+    fn bad_entries_are_refused() {
+        assert!(AttackRules::parse("clear 0 0 1 2").is_err());
+        assert!(AttackRules::parse("tspin 0 2 4 6 8").is_err());
+        assert!(AttackRules::parse("combo").is_err());
+        assert!(AttackRules::parse("back_to_back lots").is_err());
+        assert!(AttackRules::parse("cancel maybe").is_err());
+        assert!(AttackRules::parse("garbage_cap -1").is_err());
+        assert!(AttackRules::parse("gravity 20").is_err());
+    }
+
+    #[test]
+    fn messiness_is_a_percentage() {
+        assert_eq!(AttackRules::parse("messiness 100").expect("rules should load").messiness, 100);
+        assert!(AttackRules::parse("messiness 250").is_err());
+    }
+
+    #[test]
+    fn tetrises_in_a_row_get_the_back_to_back_bonus() {
+        let mut attack = Attack::new(AttackRules { combo: vec![0], ..AttackRules::default() }, 1);
+        let mut tetris = Tetris::from_seed(1);
+        attack.locked(clear(4), &mut tetris, Duration::ZERO);
+        assert_eq!(attack.send(), 4);
+        attack.locked(clear(4), &mut tetris, Duration::ZERO);
+        assert_eq!(attack.send(), 5);
+        attack.locked(clear(1), &mut tetris, Duration::ZERO);
+        attack.locked(clear(4), &mut tetris, Duration::ZERO);
+        assert_eq!(attack.send(), 4);
+    }
+
+    #[test]
+    fn sent_lines_cancel_the_garbage_in_the_meter() {
+        let mut attack = Attack::new(AttackRules::default(), 1);
+        let mut tetris = Tetris::from_seed(1);
+        attack.receive(3, Duration::ZERO);
+        attack.locked(clear(2), &mut tetris, Duration::ZERO);
+        assert_eq!((attack.pending(), attack.send()), (2, 0));
+        attack.locked(clear(4), &mut tetris, Duration::ZERO);
+        assert_eq!((attack.pending(), attack.send()), (0, 2));
+    }
+
+    #[test]
+    fn garbage_rises_after_its_delay_up_to_the_cap() {
+        let rules = AttackRules { garbage_delay: Duration::from_secs(1), garbage_cap: 2, ..AttackRules::default() };
+        let mut attack = Attack::new(rules, 1);
+        let mut tetris = Tetris::from_seed(1);
+        attack.receive(3, Duration::ZERO);
+        assert!(attack.locked(clear(0), &mut tetris, Duration::from_millis(500)));
+        assert_eq!(garbage_rows(&tetris), 0);
+        assert!(attack.locked(clear(0), &mut tetris, Duration::from_secs(1)));
+        assert_eq!((garbage_rows(&tetris), attack.pending()), (2, 1));
+        assert!(attack.locked(clear(0), &mut tetris, Duration::from_secs(2)));
+        assert_eq!((garbage_rows(&tetris), attack.pending()), (3, 0));
+    }
+}
@@ -0,0 +1,45 @@
+//➔ Engine clock that can be frozen while the game is paused.
+
+use std::time::{Duration, Instant};
+
+#[derive(Debug)]
+pub struct GameClock {
+    started: Instant,
+    offset: Duration,
+    paused_at: Option<Instant>,
+    paused_for: Duration,
+}
+
+impl GameClock {
+    pub fn new() -> GameClock {
+        GameClock::starting_at(Duration::ZERO)
+    }
+
+    //➔ A clock that already shows `elapsed`, used when continuing a saved game
+    pub fn starting_at(elapsed: Duration) -> GameClock {
+        GameClock {
+            started: Instant::now(),
+            offset: elapsed,
+            paused_at: None,
+            paused_for: Duration::ZERO,
+        }
+    }
+
+    //➔ Time the game has been running, not counting pauses
+    pub fn now(&self) -> Duration {
+        let until = self.paused_at.unwrap_or_else(Instant::now);
+        self.offset + until.duration_since(self.started).saturating_sub(self.paused_for)
+    }
+
+    pub fn pause(&mut self) {
+        if self.paused_at.is_none() {
+            self.paused_at = Some(Instant::now());
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if let Some(paused_at) = self.paused_at.take() {
+            self.paused_for += paused_at.elapsed();
+        }
+    }
+}
@@ -0,0 +1,134 @@
+//➔ Daily challenge: one game a day, the same for everyone playing it that day.
+//
+// The day, counted in UTC days since 1970, gives the seed of the pieces and
+// picks the mode, its options and the variant, so every player gets the same
+// game on the same day. Only the first attempt of a day is ranked, on a
+// leaderboard of its own for that day; the attempts started on every day are
+// counted in the daily file.
+
+use crate::game::GameSetup;
+use crate::mode::{self, GameMode, DIG_LINES, DIG_MESSINESS, INVISIBLE_FADE, SPRINT_LINES, ULTRA_SECONDS};
+use crate::variant::Variant;
+use crate::{read_from_file, write_into_file, Randomizer};
+
+use std::io;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const DAILY_FILE: &str = "daily.txt";
+//➔ Days shown on the daily results screen, today first
+pub const RESULT_DAYS: u32 = 7;
+
+//➔ Modes a daily challenge can be, the ones that are ranked and do not last too long
+const DAILY_MODES: [GameMode; 5] = [GameMode::Sprint, GameMode::Ultra, GameMode::Dig, GameMode::Survival,
+    GameMode::Invisible];
+
+//➔ Today, in UTC days since 1970
+pub fn today() -> u32 {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    (now.as_secs() / (24 * 60 * 60)) as u32
+}
+
+//➔ The date of `day` as year, month and day: 2026-10-19
+pub fn date_text(day: u32) -> String {
+    //➔ counted from 0000-03-01, so leap days fall at the end of the years
+    let days = day as i64 + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day_of_month = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 { month_from_march + 3 } else { month_from_march - 9 };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    format!("{}-{:02}-{:02}", year, month, day_of_month)
+}
+
+//➔ The game of `day`: the seed is the same for everyone, and so are the mode and options drawn from it
+pub fn daily_setup(day: u32) -> GameSetup {
+    let seed = (day as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    //➔ flipped so the picks do not follow the same numbers as the pieces
+    let mut picks = Randomizer::from_seed(!seed);
+    let mode = DAILY_MODES[picks.next_below(DAILY_MODES.len() as u32) as usize];
+    let variant = Variant::ALL[picks.next_below(Variant::ALL.len() as u32) as usize];
+    //➔ the longest sprints and digs are left out, a daily is meant to be short
+    GameSetup {
+        mode,
+        variant,
+        seed: Some(seed),
+        sprint_lines: pick(&mut picks, &SPRINT_LINES[..2]),
+        ultra_seconds: pick(&mut picks, &ULTRA_SECONDS[..2]),
+        dig_lines: pick(&mut picks, &DIG_LINES[..2]),
+        dig_messiness: pick(&mut picks, &DIG_MESSINESS),
+        invisible_fade: pick(&mut picks, &INVISIBLE_FADE),
+        daily: Some(day),
+        ..GameSetup::default()
+    }
+}
+
+fn pick(picks: &mut Randomizer, values: &[u32]) -> u32 {
+    values[picks.next_below(values.len() as u32) as usize]
+}
+
+//➔ What the game of `day` is: SPRINT 40 MIRROR
+pub fn challenge_text(day: u32) -> String {
+    let setup = daily_setup(day);
+    mode::leaderboard_title(&GameSetup { daily: None, ..setup })
+}
+
+fn read_attempts() -> Vec<(u32, u32)> {
+    //➔ Without a daily file yet, no day was played
+    let content = read_from_file(DAILY_FILE).unwrap_or_default();
+    content.lines().filter_map(|line| {
+        let mut numbers = line.split_whitespace().map(|number| number.parse::<u32>().ok());
+        Some((numbers.next()??, numbers.next()??))
+    }).collect()
+}
+
+//➔ Attempts started on `day`
+pub fn attempts(day: u32) -> u32 {
+    read_attempts().into_iter().find(|&(attempt_day, _)| attempt_day == day).map_or(0, |(_, count)| count)
+}
+
+//➔ Counts one more attempt on `day`, to be done as the game starts so leaving it still counts
+pub fn add_attempt(day: u32) -> io::Result<()> {
+    let mut all = read_attempts();
+    match all.iter_mut().find(|(attempt_day, _)| *attempt_day == day) {
+        Some((_, count)) => *count += 1,
+        None => all.push((day, 1)),
+    }
+    let content = all.iter().map(|(day, count)| format!("{} {}\n", day, count)).collect::<String>();
+    write_into_file(&content, DAILY_FILE)
+}
+
+//➔ Whether the game being played on `day` is its first attempt, the only ranked one
+pub fn is_ranked(day: u32) -> bool {
+    attempts(day) <= 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn days_are_shown_as_their_date() {
+        assert_eq!(date_text(0), "1970-01-01");
+        assert_eq!(date_text(10_956), "1999-12-31");
+        assert_eq!(date_text(11_017), "2000-03-01");
+        assert_eq!(date_text(19_782), "2024-02-29");
+        assert_eq!(date_text(19_783), "2024-03-01");
+        assert_eq!(date_text(20_745), "2026-10-19");
+    }
+
+    #[test]
+    fn same_day_gives_the_same_game() {
+        for day in [0, 19_782, 20_745] {
+            let (first, again) = (daily_setup(day), daily_setup(day));
+            assert_eq!(first.seed, again.seed);
+            assert_eq!(first.mode, again.mode);
+            assert_eq!(first.variant, again.variant);
+            assert_eq!(challenge_text(day), challenge_text(day));
+            assert_eq!(first.daily, Some(day));
+        }
+        assert_ne!(daily_setup(20_745).seed, daily_setup(20_746).seed);
+    }
+}
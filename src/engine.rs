@@ -0,0 +1,459 @@
+//➔ The rules of a single board, advanced in fixed steps of one millisecond.
+//
+// What happens on the board only depends on the actions fed to the engine and
+// the engine time they happened at, never on how often frames are drawn. The
+// same seed, handling and actions therefore always give the same game, which is
+// what replays rely on.
+
+use crate::attack::Attack;
+use crate::garbage::{Dig, RisingGarbage};
+use crate::handling::{AutoShift, Handling};
+use crate::input::TimedAction;
+use crate::invisible::Invisible;
+use crate::master::{self, Master};
+use crate::mission::{Missions, Status};
+use crate::puzzle::Goal;
+use crate::settings::Action;
+use crate::zen::Zen;
+use crate::Tetris;
+
+use std::time::Duration;
+
+const STEP: Duration = Duration::from_millis(1);
+//➔ Garbage rows a dig race keeps at the bottom of the board
+const DIG_ROWS: u32 = 8;
+//➔ Rising garbage rows for every level up in survival
+const RISING_ROWS_PER_LEVEL: u32 = 5;
+
+//➔ What a piece did as it locked
+#[derive(Debug, Clone, Copy)]
+pub struct LockResult {
+    pub lines: u32,
+    //➔ the piece was a T that turned into place, see `Tetrimino::is_t_spin`
+    pub t_spin: bool,
+    //➔ the lines cleared left the board empty
+    pub perfect_clear: bool,
+}
+
+//➔ Keys that keep acting for as long as they are held down
+#[derive(Default)]
+struct HeldKeys {
+    shift: AutoShift,
+    soft_drop: bool,
+}
+
+pub struct Engine {
+    pub tetris: Tetris,
+    //➔ engine time the board has been simulated up to
+    pub time: Duration,
+    //➔ engine time of the last drop
+    pub timer: Duration,
+    held: HeldKeys,
+    //➔ set once a new piece does not fit anymore or garbage pushed blocks out, a puzzle ran out
+    //   of pieces or a mission was missed
+    pub topped_out: bool,
+    //➔ lines to clear for the game to be won, if the mode has such a goal
+    pub line_goal: Option<u32>,
+    //➔ engine time at which the game ends, if the mode is played against the clock
+    pub time_limit: Option<Duration>,
+    //➔ garbage lines to clear for the game to be won
+    pub garbage_goal: Option<u32>,
+    //➔ garbage coming in from the bottom in a dig race
+    pub dig: Option<Dig>,
+    //➔ garbage rising from the bottom in survival
+    pub rising: Option<RisingGarbage>,
+    //➔ fixed gravity, undo and no top out in zen
+    pub zen: Option<Zen>,
+    //➔ entry and lock delays, sections and grades in master
+    pub master: Option<Master>,
+    //➔ locked blocks fading out in the invisible mode
+    pub invisible: Option<Invisible>,
+    //➔ pieces placed between two flips of the board, in mirror games
+    pub mirror_every: Option<u32>,
+    //➔ what solves the puzzle being played
+    pub puzzle_goal: Option<Goal>,
+    //➔ the run through the missions of the mission mode
+    pub missions: Option<Missions>,
+    //➔ garbage sent to and received from the opponent in versus
+    pub attack: Option<Attack>,
+    //➔ the last move of the current piece was a rotation, for telling T-spins
+    rotated: bool,
+    //➔ what the last piece locked did
+    pub last_lock: Option<LockResult>,
+    //➔ set once the goal of the mode is reached
+    pub finished: bool,
+    //➔ engine time of the first action pressed, where timed modes start counting
+    pub first_input: Option<Duration>,
+    //➔ engine time at which every line was cleared, the first line first
+    pub line_times: Vec<Duration>,
+    //➔ when recording, every action applied along with the step it was applied at
+    pub recording: Option<Vec<TimedAction>>,
+}
+
+impl Engine {
+    pub fn new(tetris: Tetris, time: Duration, timer: Duration) -> Engine {
+        Engine {
+            tetris,
+            time,
+            timer,
+            held: HeldKeys::default(),
+            topped_out: false,
+            line_goal: None,
+            time_limit: None,
+            garbage_goal: None,
+            dig: None,
+            rising: None,
+            zen: None,
+            master: None,
+            invisible: None,
+            mirror_every: None,
+            puzzle_goal: None,
+            missions: None,
+            attack: None,
+            rotated: false,
+            last_lock: None,
+            finished: false,
+            first_input: None,
+            line_times: Vec::new(),
+            recording: None,
+        }
+    }
+
+    //➔ Forgets the held keys, for when their release cannot reach the engine (e.g. while paused)
+    pub fn release_all(&mut self) {
+        self.held = HeldKeys::default();
+    }
+
+    //➔ Runs the game up to `now`, applying each action at the step it happened on.
+    //   Actions from before the current step are applied right away
+    pub fn advance(&mut self, actions: &[TimedAction], now: Duration, handling: &Handling) {
+        let mut actions = actions.iter().peekable();
+        while self.time < now && !self.topped_out && !self.finished {
+            self.time += STEP;
+            if !self.spawn() {
+                return;
+            }
+            while let Some(action) = actions.next_if(|action| action.time <= self.time) {
+                self.apply(action.action, action.pressed);
+                if let Some(ref mut recording) = self.recording {
+                    recording.push(TimedAction { time: self.time, ..*action });
+                }
+            }
+            self.shift(handling);
+            self.fall(handling);
+            self.rise_garbage();
+            self.check_goal();
+        }
+    }
+
+    //➔ Notes when lines were cleared and whether that or the time limit reached the goal
+    fn check_goal(&mut self) {
+        if self.time_limit.is_some_and(|limit| self.time >= limit) {
+            self.finished = true;
+        }
+        while (self.line_times.len() as u32) < self.tetris.num_lines {
+            self.line_times.push(self.time);
+        }
+        if self.line_goal.is_some_and(|goal| self.tetris.num_lines >= goal) {
+            self.finished = true;
+        }
+        if self.garbage_goal.is_some_and(|goal| self.tetris.garbage_cleared >= goal) {
+            self.finished = true;
+        }
+        if self.master.is_some() && self.tetris.current_level >= master::MAX_LEVEL {
+            self.finished = true;
+        }
+        let last_lock = self.last_lock;
+        let solved = match self.puzzle_goal {
+            Some(Goal::ClearAll) => last_lock.is_some_and(|lock| lock.perfect_clear),
+            Some(Goal::Lines(lines)) => self.tetris.num_lines >= lines,
+            Some(Goal::TSpinDouble) => last_lock.is_some_and(|lock| lock.t_spin && lock.lines == 2),
+            Some(Goal::Survive(pieces)) => self.tetris.pieces_placed() >= pieces,
+            None => false,
+        };
+        if solved {
+            self.finished = true;
+        }
+        if let Some(ref mut missions) = self.missions {
+            match missions.update(&mut self.tetris, self.time) {
+                Status::Playing => {},
+                Status::Failed => self.topped_out = true,
+                Status::Completed => self.finished = true,
+            }
+        }
+    }
+
+    //➔ Time since the first input, which is how long a timed game took so far
+    pub fn run_time(&self) -> Duration {
+        self.first_input.map_or(Duration::ZERO, |first| self.time.saturating_sub(first))
+    }
+
+    //➔ Time until the time limit, if there is one
+    pub fn time_left(&self) -> Option<Duration> {
+        self.time_limit.map(|limit| limit.saturating_sub(self.time))
+    }
+
+    //➔ Brings in the next piece when there is none and its entry delay is over, returns false on top out
+    fn spawn(&mut self) -> bool {
+        if self.master.as_ref().is_some_and(|master| self.time < master.next_spawn) {
+            return true;
+        }
+        if self.tetris.current_piece.is_none() {
+            if !self.refill_garbage() {
+                self.topped_out = true;
+                return false;
+            }
+            //➔ a puzzle that used up its pieces without being solved is lost
+            if !self.tetris.deals && self.tetris.next_kinds.is_empty() {
+                self.topped_out = true;
+                return false;
+            }
+            let placed = self.tetris.pieces_placed();
+            if self.mirror_every.is_some_and(|every| placed > 0 && placed.is_multiple_of(every)) {
+                self.tetris.mirror();
+            }
+            let piece = self.tetris.create_new_tetrimino();
+            if !piece.test_current_position(&self.tetris.game_map) {
+                //➔ zen never ends, the board is cleared instead so the new piece fits
+                if self.zen.is_none() {
+                    self.topped_out = true;
+                    return false;
+                }
+                self.tetris.clear_board();
+            }
+            self.tetris.current_piece = Some(piece);
+            self.rotated = false;
+            if let Some(ref mut master) = self.master {
+                master.enter(&mut self.tetris, self.time);
+            }
+            if let Some(ref mut zen) = self.zen {
+                zen.snapshot(&self.tetris);
+            }
+        }
+        true
+    }
+
+    //➔ Tops the garbage of a dig race back up between two pieces, returns false if the board overflowed
+    fn refill_garbage(&mut self) -> bool {
+        let dig = match self.dig {
+            Some(ref mut dig) => dig,
+            None => return true,
+        };
+        let width = self.tetris.width;
+        //➔ a big board only has room for half as many
+        let rows = DIG_ROWS.min(self.tetris.height as u32 / 2);
+        while dig.rows_left > 0 && self.tetris.garbage_rows() < rows {
+            dig.rows_left -= 1;
+            if !self.tetris.add_garbage(dig.generator.next_row(width)) {
+                return false;
+            }
+        }
+        true
+    }
+
+    //➔ Pushes in a survival row once its time came. The gravity speeds up along with the rows
+    fn rise_garbage(&mut self) {
+        let rising = match self.rising {
+            Some(ref mut rising) => rising,
+            None => return,
+        };
+        if self.time < rising.next_time {
+            return;
+        }
+        rising.rows += 1;
+        rising.next_time = self.time + rising.interval();
+        if rising.rows.is_multiple_of(RISING_ROWS_PER_LEVEL) {
+            self.tetris.inc_level();
+        }
+        let width = self.tetris.width;
+        if !self.tetris.add_garbage(rising.generator.next_row(width)) || !self.tetris.push_piece_up() {
+            self.topped_out = true;
+        }
+    }
+
+    //➔ Time until the next survival row rises
+    pub fn time_to_rise(&self) -> Option<Duration> {
+        self.rising.as_ref().map(|rising| rising.next_time.saturating_sub(self.time))
+    }
+
+    fn apply(&mut self, action: Action, pressed: bool) {
+        let now = self.time;
+        if pressed && self.first_input.is_none() {
+            self.first_input = Some(now);
+        }
+        //➔ held keys are tracked even without a piece, so they act on the next one
+        match (action, pressed) {
+            (Action::MoveLeft, true) => self.held.shift.press(-1, now),
+            (Action::MoveRight, true) => self.held.shift.press(1, now),
+            (Action::MoveLeft, false) => self.held.shift.release(-1, now),
+            (Action::MoveRight, false) => self.held.shift.release(1, now),
+            (Action::SoftDrop, pressed) => self.held.soft_drop = pressed,
+            _ => {},
+        }
+        if !pressed {
+            return;
+        }
+        //➔ the piece coming out of hold starts falling from the top with a full gravity delay
+        if action == Action::Hold && self.tetris.hold() {
+            self.timer = now;
+            self.rotated = false;
+            if let Some(ref mut master) = self.master {
+                master.reset_piece();
+            }
+        }
+        if action == Action::Undo {
+            if let Some(tetris) = self.zen.as_mut().and_then(|zen| zen.undo()) {
+                self.tetris = tetris;
+                self.timer = now;
+            }
+            return;
+        }
+        let tetris = &mut self.tetris;
+        let piece = match tetris.current_piece {
+            Some(ref mut piece) => piece,
+            //➔ what is pressed during the entry delay of master goes to the next piece
+            None => {
+                if let Some(ref mut master) = self.master {
+                    master.buffer(action);
+                }
+                return;
+            },
+        };
+        let ruleset = tetris.ruleset;
+        let state = piece.current_state;
+        match action {
+            Action::MoveLeft if piece.change_position(&tetris.game_map, piece.x - 1, piece.y) => self.rotated = false,
+            Action::MoveRight if piece.change_position(&tetris.game_map, piece.x + 1, piece.y) => self.rotated = false,
+            Action::Rotate | Action::RotateCcw | Action::Rotate180 => {
+                let step = match action {
+                    Action::Rotate => 1,
+                    Action::RotateCcw => -1,
+                    _ => 2,
+                };
+                let kicks = ruleset.kicks(piece, &tetris.game_map, step);
+                piece.rotate_by(&tetris.game_map, step, kicks);
+            },
+            Action::HardDrop => {
+                let drop_position = piece.drop_position(&tetris.game_map);
+                let cells = drop_position - piece.y;
+                piece.y = drop_position;
+                if cells > 0 {
+                    self.rotated = false;
+                }
+                tetris.add_drop_points(cells, true);
+                self.lock();
+                self.timer = now;
+                return;
+            },
+            _ => {},
+        }
+        if piece.current_state != state {
+            self.rotated = true;
+        }
+    }
+
+    //➔ Locks the current piece into the board
+    fn lock(&mut self) {
+        let t_spin = self.rotated && self.tetris.current_piece.as_ref()
+            .is_some_and(|piece| piece.is_t_spin(&self.tetris.game_map));
+        let lines = self.tetris.num_lines;
+        self.tetris.make_permanent(self.time, t_spin);
+        let lines = self.tetris.num_lines - lines;
+        let perfect_clear = lines > 0 && self.tetris.game_map.iter().all(|row| row.iter().all(|&block| block == 0));
+        let lock = LockResult { lines, t_spin, perfect_clear };
+        self.last_lock = Some(lock);
+        if let Some(ref mut missions) = self.missions {
+            missions.locked(lock);
+        }
+        if let Some(ref mut attack) = self.attack {
+            if !attack.locked(lock, &mut self.tetris, self.time) {
+                self.topped_out = true;
+            }
+        }
+        if let Some(ref mut master) = self.master {
+            master.locked(&mut self.tetris, lines, self.time);
+        }
+    }
+
+    fn shift(&mut self, handling: &Handling) {
+        let cells = self.held.shift.shifts(self.time, handling.das, handling.arr);
+        if let Some(ref mut piece) = self.tetris.current_piece {
+            for _ in 0..cells.abs() {
+                if !piece.change_position(&self.tetris.game_map, piece.x + cells.signum() as isize, piece.y) {
+                    break;
+                }
+                self.rotated = false;
+            }
+        }
+    }
+
+    //➔ While soft dropping, gravity is `handling.sdf` times faster. Without gravity
+    //   in zen, soft dropping goes at `handling.sdf` times the speed of level 1
+    fn fall(&mut self, handling: &Handling) {
+        if self.master.is_some() {
+            self.fall_master();
+            return;
+        }
+        let soft_drop_factor = if self.held.soft_drop { handling.sdf } else { 1 };
+        let level = match self.zen {
+            Some(ref zen) if zen.gravity == 0 && !self.held.soft_drop => return,
+            Some(ref zen) => zen.gravity.max(1),
+            None => self.tetris.current_level,
+        };
+        //➔ 20G missions drop the piece to the floor at once, it then locks once its gravity delay is over
+        if self.missions.as_ref().is_some_and(|missions| missions.twenty_g()) {
+            if let Some(ref mut piece) = self.tetris.current_piece {
+                let drop_position = piece.drop_position(&self.tetris.game_map);
+                if drop_position != piece.y {
+                    piece.y = drop_position;
+                    self.rotated = false;
+                }
+            }
+        }
+        let millis = self.time.saturating_sub(self.timer).as_millis() as u32;
+        if millis <= self.tetris.ruleset.drop_time(level) / soft_drop_factor {
+            return;
+        }
+        if let Some(ref mut piece) = self.tetris.current_piece {
+            let x = piece.x;
+            let y = piece.y + 1;
+            if !piece.change_position(&self.tetris.game_map, x, y) {
+                self.lock();
+            } else {
+                self.rotated = false;
+                if self.held.soft_drop {
+                    self.tetris.add_drop_points(1, false);
+                }
+            }
+        }
+        self.timer = self.time;
+    }
+
+    //➔ Master gravity can drop a piece several rows in a step. A piece on the ground locks once
+    //   its lock delay ran out, or right away while soft dropping
+    fn fall_master(&mut self) {
+        let level = self.tetris.current_level;
+        let (master, piece) = match (self.master.as_mut(), self.tetris.current_piece.as_mut()) {
+            (Some(master), Some(piece)) => (master, piece),
+            _ => return,
+        };
+        let mut rows = 0;
+        for _ in 0..master.fall_rows(level, self.held.soft_drop) {
+            if !piece.change_position(&self.tetris.game_map, piece.x, piece.y + 1) {
+                break;
+            }
+            rows += 1;
+        }
+        if rows > 0 {
+            self.rotated = false;
+        }
+        let on_ground = !piece.test_position(&self.tetris.game_map, piece.current_state, piece.x, piece.y + 1);
+        let lock = master.lock_due(level, on_ground, rows > 0, self.time) || (on_ground && self.held.soft_drop);
+        if self.held.soft_drop {
+            self.tetris.add_drop_points(rows, false);
+        }
+        if lock {
+            self.lock();
+        }
+    }
+}
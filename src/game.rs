@@ -0,0 +1,722 @@
+//➔ A single game, from the first piece until game over or until the player leaves it.
+
+use crate::clock::GameClock;
+use crate::daily;
+use crate::engine::Engine;
+use crate::gamepad::{self, Gamepads};
+use crate::graphics::Graphics;
+use crate::garbage::{Dig, GarbageGenerator, RisingGarbage};
+use crate::handling::Handling;
+use crate::hud::draw_hud;
+use crate::input::{DeviceInput, InputSource, ReplayInput};
+use crate::invisible::{self, Invisible};
+use crate::master::Master;
+use crate::mission::{self, Missions};
+use crate::menu::{Menu, MenuInput};
+use crate::mode::{self, GameMode, SURVIVAL_MESSINESS};
+use crate::puzzle::{self, Puzzle};
+use crate::replay::{Replay, REPLAY_FILE};
+use crate::ruleset::Ruleset;
+use crate::scoring::Scoring;
+use crate::settings::{Action, Settings};
+use crate::variant::{Variant, MIRROR_PIECES};
+use crate::zen::Zen;
+use crate::{print_game_information, save, screens, Tetris, BOARD_HEIGHT, BOARD_WIDTH, TETRIS_HEIGHT};
+use crate::save::invalid;
+use crate::screens::GameOutcome;
+
+use sdl2::controller::Button;
+use sdl2::event::{Event, WindowEvent};
+use sdl2::keyboard::Keycode;
+
+use std::error::Error;
+use std::io;
+use std::thread;
+use std::time::Duration;
+
+//➔ Seconds of play between two automatic saves
+const AUTOSAVE_INTERVAL: u64 = 30;
+//➔ How long the board of an invisible game is shown once it is over
+const REVEAL_TIME: Duration = Duration::from_secs(3);
+
+//➔ What to do once a game is over
+pub enum GameEnd {
+    //➔ go back to the title screen
+    Menu,
+    //➔ the window was closed, leave the program
+    Exit,
+    //➔ play again right away with the same setup, with this seed or a new one
+    Retry(Option<u64>),
+    //➔ play the next puzzle of the pack
+    NextPuzzle,
+}
+
+//➔ Everything picked on the title screen before starting a game
+#[derive(Debug, Clone)]
+pub struct GameSetup {
+    pub mode: GameMode,
+    pub ruleset: Ruleset,
+    pub variant: Variant,
+    pub start_level: u32,
+    //➔ deals the pieces of an earlier game again, a random seed is used when there is none
+    pub seed: Option<u64>,
+    //➔ lines to clear in a sprint
+    pub sprint_lines: u32,
+    //➔ length of an ultra, in seconds
+    pub ultra_seconds: u32,
+    //➔ lines that end a marathon, 0 for an endless one
+    pub marathon_goal: u32,
+    //➔ garbage lines to clear in a dig race
+    pub dig_lines: u32,
+    //➔ chance in percent for the hole to move between two garbage rows
+    pub dig_messiness: u32,
+    //➔ level whose gravity zen is played with, 0 for none
+    pub zen_gravity: u32,
+    //➔ placements zen can undo
+    pub zen_undo: u32,
+    //➔ seconds locked blocks stay shown in the invisible mode
+    pub invisible_fade: u32,
+    //➔ pack of the puzzle to play, and its place in the pack from 0
+    pub puzzle_pack: String,
+    pub puzzle_index: usize,
+    //➔ the day of the daily challenge being played, see `daily::daily_setup`
+    pub daily: Option<u32>,
+}
+
+impl Default for GameSetup {
+    fn default() -> GameSetup {
+        GameSetup {
+            mode: GameMode::Marathon,
+            ruleset: Ruleset::Standard,
+            variant: Variant::Normal,
+            start_level: 1,
+            seed: None,
+            sprint_lines: 40,
+            ultra_seconds: 120,
+            marathon_goal: 0,
+            dig_lines: 18,
+            dig_messiness: 25,
+            zen_gravity: 0,
+            zen_undo: 20,
+            invisible_fade: 0,
+            puzzle_pack: puzzle::pack_names().into_iter().next().unwrap_or_default(),
+            puzzle_index: 0,
+            daily: None,
+        }
+    }
+}
+
+impl GameSetup {
+    //➔ Lines that end the game once cleared, for the modes that have such a goal
+    pub fn line_goal(&self) -> Option<u32> {
+        match self.mode {
+            GameMode::Marathon => Some(self.marathon_goal).filter(|&goal| goal > 0),
+            GameMode::Ultra | GameMode::Dig | GameMode::Survival | GameMode::Zen | GameMode::Master |
+            GameMode::Invisible | GameMode::Puzzle | GameMode::Mission => None,
+            GameMode::Sprint => Some(self.sprint_lines),
+        }
+    }
+
+    //➔ How long the game lasts, for the modes played against the clock
+    pub fn time_limit(&self) -> Option<Duration> {
+        match self.mode {
+            GameMode::Marathon | GameMode::Sprint | GameMode::Dig | GameMode::Survival | GameMode::Zen |
+            GameMode::Master | GameMode::Invisible | GameMode::Puzzle | GameMode::Mission => None,
+            GameMode::Ultra => Some(Duration::from_secs(self.ultra_seconds as u64)),
+        }
+    }
+
+    //➔ Whether the time shown is the time since the first input, rather than since the game started
+    pub fn is_timed(&self) -> bool {
+        matches!(self.mode, GameMode::Sprint | GameMode::Dig)
+    }
+
+    //➔ The garbage of a dig race, drawn from the seed of the game
+    pub fn dig(&self, seed: u64) -> Option<Dig> {
+        if self.mode != GameMode::Dig {
+            return None;
+        }
+        Some(Dig {
+            //➔ flipped so the holes do not follow the same numbers as the pieces
+            generator: GarbageGenerator::new(!seed, self.dig_messiness),
+            rows_left: self.dig_lines,
+        })
+    }
+
+    //➔ The rising garbage of survival, drawn from the seed of the game like the dig garbage
+    pub fn rising(&self, seed: u64) -> Option<RisingGarbage> {
+        if self.mode != GameMode::Survival {
+            return None;
+        }
+        Some(RisingGarbage::new(GarbageGenerator::new(!seed, SURVIVAL_MESSINESS)))
+    }
+
+    //➔ The rules the game is played by, which are not up to the player in master
+    pub fn rules(&self) -> Ruleset {
+        if self.mode.picks_rules() {
+            self.ruleset
+        } else {
+            Ruleset::Ars
+        }
+    }
+
+    //➔ The same seed always deals the same pieces
+    pub fn new_game(&self, seed: u64) -> Tetris {
+        let ruleset = self.rules();
+        let (min_level, max_level) = ruleset.start_levels();
+        let start_level = self.start_level.clamp(min_level, max_level);
+        let mut tetris = Tetris::with_ruleset(seed, ruleset);
+        tetris.current_level = start_level;
+        tetris.start_level = start_level;
+        tetris.scoring = if ruleset == Ruleset::Nes { Scoring::Nes } else { self.mode.scoring() };
+        //➔ puzzle boards are made for the normal size
+        if self.variant == Variant::Big && self.mode != GameMode::Puzzle {
+            tetris.resize(BOARD_WIDTH / 2, BOARD_HEIGHT / 2);
+        }
+        tetris
+    }
+
+    //➔ The puzzle picked, along with how many puzzles its pack has
+    pub fn puzzle(&self) -> io::Result<(Puzzle, usize)> {
+        let puzzles = puzzle::load_pack(&self.puzzle_pack)?;
+        let count = puzzles.len();
+        let puzzle = puzzles.into_iter().nth(self.puzzle_index)
+            .ok_or_else(|| invalid(format!("pack `{}` has no puzzle {}", self.puzzle_pack, self.puzzle_index + 1)))?;
+        Ok((puzzle, count))
+    }
+
+    //➔ Checks the files the mode is played from can be read, before starting a game of it
+    pub fn check_files(&self) -> io::Result<()> {
+        match self.mode {
+            GameMode::Puzzle => self.puzzle().map(|_| ()),
+            GameMode::Mission => mission::load_missions().map(|_| ()),
+            _ => Ok(()),
+        }
+    }
+
+    //➔ Whether the pack has a puzzle after the one picked
+    pub fn has_next_puzzle(&self) -> bool {
+        self.mode == GameMode::Puzzle && self.puzzle().is_ok_and(|(_, count)| self.puzzle_index + 1 < count)
+    }
+
+    //➔ Why the game cannot be saved, if it cannot: only normal games by the standard rules can be,
+    //   the save file keeps neither the ruleset nor the variant
+    pub fn save_blocker(&self) -> Option<&'static str> {
+        if let Some(blocker) = self.mode.save_blocker() {
+            Some(blocker)
+        } else if self.rules() != Ruleset::Standard {
+            Some("ONLY STANDARD RULES CAN BE SAVED")
+        } else if self.variant != Variant::Normal {
+            Some("VARIANTS CANNOT BE SAVED")
+        } else {
+            None
+        }
+    }
+
+    //➔ The highscore file only holds normal standard marathons, so their scores compare
+    fn keeps_highscores(&self) -> bool {
+        self.mode == GameMode::Marathon && self.rules() == Ruleset::Standard && self.variant == Variant::Normal
+    }
+}
+
+//➔ Seed for a restarted game, depending on whether the player wants the same pieces again.
+//   A daily challenge always restarts with the seed of its day
+pub fn restart_seed(settings: &Settings, setup: &GameSetup, tetris: &Tetris) -> Option<u64> {
+    if setup.daily.is_some() {
+        setup.seed
+    } else if settings.restart_same_seed {
+        Some(tetris.seed)
+    } else {
+        None
+    }
+}
+
+enum Request {
+    Pause,
+    Restart,
+    WindowClosed,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum PauseChoice {
+    Resume,
+    Restart,
+    Settings,
+    SaveAndQuit,
+    //➔ tells why the game cannot be saved, choosing it does nothing
+    NoSave,
+    Quit,
+}
+
+//➔ The pause menu, offering to save unless there is a `save_blocker` to tell instead
+pub fn pause_menu(save_blocker: Option<&str>) -> Menu<PauseChoice> {
+    let mut items = vec![
+        ("RESUME".to_string(), PauseChoice::Resume),
+        ("RESTART".to_string(), PauseChoice::Restart),
+        ("SETTINGS".to_string(), PauseChoice::Settings),
+    ];
+    match save_blocker {
+        None => items.push(("SAVE & QUIT".to_string(), PauseChoice::SaveAndQuit)),
+        Some(blocker) => items.push((blocker.to_string(), PauseChoice::NoSave)),
+    }
+    items.push(("QUIT".to_string(), PauseChoice::Quit));
+    Menu::new("PAUSED", items)
+}
+
+//➔ Handles the window and controller events of this frame, keys and buttons go to `input`
+fn handle_events(settings: &Settings, gamepads: &mut Gamepads, input: &mut DeviceInput, now: Duration,
+    event_pump: &mut sdl2::EventPump) -> Option<Request> {
+    for event in event_pump.poll_iter() {
+        match event {
+            Event::Quit {..} => return Some(Request::WindowClosed),
+            Event::Window {win_event: WindowEvent::FocusLost, ..} => return Some(Request::Pause),
+            //➔ Controllers can be plugged in and out in the middle of a game
+            Event::ControllerDeviceAdded {..} | Event::ControllerDeviceRemoved {..} => gamepads.refresh(),
+            event => input.handle_event(&event, settings, gamepads, now),
+        }
+    }
+    None
+}
+
+pub fn handle_pause_events(menu: &mut Menu<PauseChoice>, settings: &Settings, event_pump: &mut sdl2::EventPump) -> Option<PauseChoice> {
+    for event in event_pump.poll_iter() {
+        if let Event::ControllerButtonDown {button: Button::Start, ..} = event {
+            return Some(PauseChoice::Resume);
+        }
+        match gamepad::as_menu_event(event) {
+            //➔ Closing the window while paused keeps the game for later
+            Event::Quit {..} => return Some(PauseChoice::SaveAndQuit),
+            Event::KeyDown {keycode: Some(keycode), ..}
+                if keycode == Keycode::Escape || settings.action_for_key(keycode) == Some(Action::Pause) => {
+                return Some(PauseChoice::Resume)
+            },
+            Event::KeyDown {keycode: Some(keycode), ..} => {
+                if let Some(MenuInput::Choose(choice)) = menu.handle_key(keycode) {
+                    return Some(choice);
+                }
+            },
+            _ => {}
+        }
+    }
+    None
+}
+
+fn save_and_leave(setup: &GameSetup, tetris: &Tetris, clock: &GameClock, timer: Duration) -> Result<(), Box<dyn Error>> {
+    save::save_game(setup, tetris, clock, timer)?;
+    println!("Game saved to {}, pick \"continue\" on the title screen to resume it.", save::SAVE_FILE);
+    Ok(())
+}
+
+//➔ The replay of the game `engine` recorded, if it still records
+fn recorded_replay(engine: &Engine, setup: &GameSetup, handling: Handling) -> Option<Replay> {
+    engine.recording.as_ref().map(|actions| Replay {
+        setup: GameSetup {
+            seed: Some(engine.tetris.seed),
+            ..setup.clone()
+        },
+        handling,
+        actions: actions.clone(),
+    })
+}
+
+fn save_replay(engine: &Engine, setup: &GameSetup, handling: Handling) {
+    if let Some(replay) = recorded_replay(engine, setup, handling) {
+        if let Err(e) = replay.save() {
+            println!("Could not save the replay to {}: {}", REPLAY_FILE, e);
+        }
+    }
+}
+
+//➔ A fresh game from `setup` with its actions being recorded, dealt from `seed` or from a random one
+fn start_game(setup: &GameSetup, seed: Option<u64>) -> Engine {
+    let seed = seed.unwrap_or_else(rand::random::<u64>);
+    let mut engine = Engine::new(setup.new_game(seed), Duration::ZERO, Duration::ZERO);
+    //➔ counted as the game starts, so leaving a daily halfway still uses up its ranked attempt
+    if let Some(day) = setup.daily {
+        if let Err(e) = daily::add_attempt(day) {
+            println!("Could not count the daily attempt in {}: {}", daily::DAILY_FILE, e);
+        }
+    }
+    engine.line_goal = setup.line_goal();
+    engine.time_limit = setup.time_limit();
+    engine.dig = setup.dig(engine.tetris.seed);
+    engine.rising = setup.rising(engine.tetris.seed);
+    if setup.mode == GameMode::Zen {
+        engine.zen = Some(Zen::new(setup.zen_gravity, setup.zen_undo));
+    }
+    if engine.dig.is_some() {
+        engine.garbage_goal = Some(setup.dig_lines);
+    }
+    if setup.mode == GameMode::Master {
+        engine.master = Some(Master::new());
+    }
+    if setup.variant == Variant::Mirror {
+        engine.mirror_every = Some(MIRROR_PIECES);
+    }
+    if setup.mode == GameMode::Invisible {
+        engine.invisible = Some(Invisible::new(Duration::from_secs(setup.invisible_fade as u64)));
+    }
+    if setup.mode == GameMode::Puzzle {
+        //➔ without its puzzle the game has no pieces, so it is over right away
+        engine.tetris.deals = false;
+        engine.tetris.next_kinds.clear();
+        match setup.puzzle() {
+            Ok((puzzle, _)) => {
+                engine.tetris.game_map = puzzle.board;
+                engine.tetris.next_kinds = puzzle.pieces.into_iter().collect();
+                engine.tetris.hold_enabled = puzzle.hold;
+                engine.puzzle_goal = Some(puzzle.goal);
+            },
+            Err(e) => println!("Could not load the puzzle: {}", e),
+        }
+    }
+    if setup.mode == GameMode::Mission {
+        match mission::load_missions() {
+            Ok(missions) => engine.missions = Some(Missions::new(missions, &mut engine.tetris, engine.time)),
+            //➔ without missions there is nothing to play
+            Err(e) => {
+                println!("Could not load the missions from {}: {}", mission::MISSION_FILE, e);
+                engine.topped_out = true;
+            },
+        }
+    }
+    engine.recording = Some(Vec::new());
+    engine
+}
+
+//➔ Time shown in the HUD: timed modes count from the first input
+fn shown_time(setup: &GameSetup, engine: &Engine, clock: &GameClock) -> Duration {
+    if setup.is_timed() {
+        engine.run_time()
+    } else {
+        clock.now()
+    }
+}
+
+//➔ Saves the result and returns the lines to show for it. Marathon, survival, master and
+//   invisible games are ranked however they ended, the other modes only when they reached their goal.
+//   Standard marathons also go into the highscore file
+fn result_lines(setup: &GameSetup, engine: &Engine, elapsed: Duration) -> Vec<String> {
+    match setup.mode {
+        GameMode::Puzzle => mode::finish(setup, engine),
+        GameMode::Marathon | GameMode::Survival | GameMode::Master | GameMode::Invisible | GameMode::Mission => {
+            if setup.keeps_highscores() {
+                print_game_information(&engine.tetris);
+            }
+            let mut lines = screens::game_summary(&engine.tetris, elapsed);
+            lines.extend(mode::finish(setup, engine));
+            lines
+        },
+        _ if engine.finished => mode::finish(setup, engine),
+        _ => screens::game_summary(&engine.tetris, elapsed),
+    }
+}
+
+pub fn draw_game(graphics: &mut Graphics, engine: &Engine, settings: &Settings, variant: Variant) -> Result<(), String> {
+    let tetris = &engine.tetris;
+    graphics.set_palette(tetris.ruleset.palette(tetris.current_level));
+    graphics.set_view((BOARD_WIDTH / tetris.width) as u32, variant == Variant::UpsideDown);
+    graphics.draw_board(true);
+    match engine.invisible {
+        Some(ref invisible) => {
+            let alphas = invisible.alphas(tetris, engine.time);
+            graphics.draw_faded_map(&tetris.game_map, |row, col| alphas[row][col]);
+            for (row_num, row) in tetris.lock_times.iter().enumerate() {
+                for (col_num, &locked_at) in row.iter().enumerate() {
+                    if tetris.game_map[row_num][col_num] != 0 && invisible::flashing(locked_at, engine.time) {
+                        graphics.draw_outline(row_num, col_num)?;
+                    }
+                }
+            }
+        },
+        None => graphics.draw_map(&tetris.game_map),
+    }
+    if let Some(ref piece) = tetris.current_piece {
+        if settings.ghost && tetris.ruleset.has_ghost() {
+            graphics.draw_ghost(piece, piece.drop_position(&tetris.game_map))?;
+        }
+        graphics.draw_piece(piece);
+    }
+    Ok(())
+}
+
+//➔ Shows the whole board of an invisible game for REVEAL_TIME, or until a key or button is pressed.
+//   Returns false if the window was closed
+fn reveal_board(graphics: &mut Graphics, event_pump: &mut sdl2::EventPump, tetris: &Tetris, variant: Variant) -> bool {
+    let clock = GameClock::new();
+    graphics.set_view((BOARD_WIDTH / tetris.width) as u32, variant == Variant::UpsideDown);
+    while clock.now() < REVEAL_TIME {
+        for event in event_pump.poll_iter() {
+            match event {
+                Event::Quit {..} => return false,
+                Event::KeyDown {repeat: false, ..} | Event::ControllerButtonDown {..} => return true,
+                _ => {},
+            }
+        }
+        graphics.clear();
+        graphics.draw_board(true);
+        graphics.draw_map(&tetris.game_map);
+        graphics.present();
+        thread::sleep(Duration::new(0, 1_000_000_000u32 / 60));
+    }
+    true
+}
+
+//➔ Plays a game, either a fresh one from `setup` or the `saved` game being continued
+pub fn run_game(graphics: &mut Graphics, event_pump: &mut sdl2::EventPump, gamepads: &mut Gamepads, settings: &mut Settings,
+    setup: &GameSetup, saved: Option<(Tetris, GameClock, Duration)>) -> Result<GameEnd, Box<dyn Error>> {
+    //➔ picks up controllers plugged in while the menus were shown
+    gamepads.refresh();
+
+    //➔ Only fresh games are recorded, a continued one misses its beginning
+    let (mut engine, mut clock) = match saved {
+        Some((tetris, clock, timer)) => {
+            let mut engine = Engine::new(tetris, clock.now(), timer);
+            engine.line_goal = setup.line_goal();
+            (engine, clock)
+        },
+        None => (start_game(setup, setup.seed), GameClock::new()),
+    };
+    let mut handling = Handling::from_settings(settings);
+    let mut input = DeviceInput::default();
+    //➔ engine time of the last autosave
+    let mut autosave_timer = clock.now();
+    let mut paused: Option<Menu<PauseChoice>> = None;
+    let can_save = setup.save_blocker().is_none();
+    let best = mode::personal_best(setup);
+
+    loop {
+        if let Some(ref mut menu) = paused {
+            match handle_pause_events(menu, settings, event_pump) {
+                Some(PauseChoice::Resume) => {
+                    paused = None;
+                    clock.resume();
+                },
+                Some(PauseChoice::Restart) => {
+                    save::remove_save();
+                    engine = start_game(setup, restart_seed(settings, setup, &engine.tetris));
+                    clock = GameClock::new();
+                    autosave_timer = Duration::ZERO;
+                    paused = None;
+                },
+                Some(PauseChoice::Settings) => {
+                    let window_open = screens::settings_screen(graphics, event_pump, gamepads, settings);
+                    if !window_open {
+                        if can_save {
+                            save_and_leave(setup, &engine.tetris, &clock, engine.timer)?;
+                        }
+                        return Ok(GameEnd::Exit);
+                    }
+                    //➔ A replay would not play back the same with the handling changed halfway
+                    let changed = Handling::from_settings(settings);
+                    if changed != handling && engine.recording.take().is_some() {
+                        println!("The handling changed during the game, it will not be saved as a replay.");
+                    }
+                    handling = changed;
+                },
+                //➔ also what closing the window while paused asks for, whether the game can be saved or not
+                Some(PauseChoice::SaveAndQuit) => {
+                    if can_save {
+                        save_and_leave(setup, &engine.tetris, &clock, engine.timer)?;
+                    }
+                    return Ok(GameEnd::Menu);
+                },
+                Some(PauseChoice::Quit) => {
+                    save::remove_save();
+                    if setup.keeps_highscores() {
+                        print_game_information(&engine.tetris);
+                    }
+                    return Ok(GameEnd::Menu);
+                },
+                Some(PauseChoice::NoSave) | None => {},
+            }
+        } else {
+            let mut request = handle_events(settings, gamepads, &mut input, clock.now(), event_pump);
+            let mut actions = input.poll(clock.now());
+            //➔ Pausing and restarting are up to the game loop, the engine only gets the actions before them
+            let game_request = actions.iter().enumerate().find_map(|(index, action)| match action.action {
+                Action::Pause if action.pressed => Some((index, Request::Pause)),
+                Action::Restart if action.pressed => Some((index, Request::Restart)),
+                _ => None,
+            });
+            if let Some((index, game_request)) = game_request {
+                actions.truncate(index);
+                request = Some(game_request);
+            }
+            engine.advance(&actions, clock.now(), &handling);
+
+            if engine.topped_out || engine.finished {
+                save::remove_save();
+                save_replay(&engine, setup, handling);
+                if engine.invisible.is_some() && !reveal_board(graphics, event_pump, &engine.tetris, setup.variant) {
+                    return Ok(GameEnd::Exit);
+                }
+                let title = match (setup.mode, engine.finished) {
+                    (GameMode::Puzzle, true) => "SOLVED",
+                    (GameMode::Puzzle, false) => "FAILED",
+                    (GameMode::Mission, true) => "ALL MISSIONS DONE",
+                    (GameMode::Mission, false) => "MISSION FAILED",
+                    (_, true) => "FINISHED",
+                    (_, false) => "GAME OVER",
+                };
+                let outcome = GameOutcome {
+                    title,
+                    lines: result_lines(setup, &engine, shown_time(setup, &engine, &clock)),
+                    next_puzzle: setup.has_next_puzzle(),
+                };
+                return Ok(screens::game_over(graphics, event_pump, gamepads, settings, setup, &engine.tetris, &outcome));
+            }
+
+            match request {
+                //➔ The clock stops while paused, so gravity and timers pick up where they left off.
+                //   Keys released while paused never reach the game, so nothing counts as held anymore
+                Some(Request::Pause) => {
+                    clock.pause();
+                    paused = Some(pause_menu(setup.save_blocker()));
+                    engine.release_all();
+                },
+                Some(Request::Restart) => {
+                    save::remove_save();
+                    engine = start_game(setup, restart_seed(settings, setup, &engine.tetris));
+                    clock = GameClock::new();
+                    autosave_timer = Duration::ZERO;
+                },
+                //➔ Closing the window saves the game as well, so an accidental close loses nothing
+                Some(Request::WindowClosed) => {
+                    if can_save {
+                        save_and_leave(setup, &engine.tetris, &clock, engine.timer)?;
+                    }
+                    return Ok(GameEnd::Exit);
+                },
+                None => {},
+            }
+
+            if can_save && clock.now().saturating_sub(autosave_timer).as_secs() >= AUTOSAVE_INTERVAL {
+                if let Err(e) = save::save_game(setup, &engine.tetris, &clock, engine.timer) {
+                    println!("Autosave failed: {}", e);
+                }
+                autosave_timer = clock.now();
+            }
+        }
+
+        graphics.clear();
+        if let Some(ref menu) = paused {
+            //➔ The board stays hidden behind the border while paused, so nobody can plan ahead
+            graphics.draw_board(false);
+            let center_x = graphics.width as i32 / 2;
+            let y = graphics.grid_y + TETRIS_HEIGHT as i32 * 2;
+            menu.draw(&mut graphics.canvas, center_x, y)?;
+        } else {
+            draw_game(graphics, &engine, settings, setup.variant)?;
+        }
+        let extra = mode::hud_entries(setup, &engine, best.as_ref());
+        draw_hud(graphics, &engine.tetris, shown_time(setup, &engine, &clock), settings.preview, &extra)?;
+        graphics.present();
+
+        //sleep enough to get the 60fps frame rate
+        thread::sleep(Duration::new(0, 1_000_000_000u32 / 60));
+    }
+}
+
+//➔ Plays the recorded game back until it is over or the player leaves with escape
+pub fn watch_replay(graphics: &mut Graphics, event_pump: &mut sdl2::EventPump, settings: &Settings,
+    replay: Replay) -> Result<GameEnd, Box<dyn Error>> {
+    let setup = replay.setup;
+    let mut engine = start_game(&setup, setup.seed);
+    engine.recording = None;
+    let mut input = ReplayInput::new(replay.actions);
+    let clock = GameClock::new();
+
+    loop {
+        for event in event_pump.poll_iter() {
+            match gamepad::as_menu_event(event) {
+                Event::Quit {..} => return Ok(GameEnd::Exit),
+                Event::KeyDown {keycode: Some(Keycode::Escape), ..} => return Ok(GameEnd::Menu),
+                _ => {},
+            }
+        }
+        let actions = input.poll(clock.now());
+        engine.advance(&actions, clock.now(), &replay.handling);
+        if engine.topped_out || engine.finished {
+            let lines = screens::game_summary(&engine.tetris, shown_time(&setup, &engine, &clock));
+            let window_open = screens::message_screen(graphics, event_pump, "REPLAY OVER", &lines);
+            return Ok(if window_open { GameEnd::Menu } else { GameEnd::Exit });
+        }
+
+        graphics.clear();
+        draw_game(graphics, &engine, settings, setup.variant)?;
+        let extra = mode::hud_entries(&setup, &engine, None);
+        draw_hud(graphics, &engine.tetris, shown_time(&setup, &engine, &clock), settings.preview, &extra)?;
+        graphics.present();
+        thread::sleep(Duration::new(0, 1_000_000_000u32 / 60));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::TimedAction;
+
+    //➔ A player spreading pieces over the board: turning, moving, soft dropping and holding
+    //   some of them before the hard drop
+    fn played_actions() -> Vec<TimedAction> {
+        let mut actions = Vec::new();
+        let mut time = Duration::ZERO;
+        let mut press = |action: Action, actions: &mut Vec<TimedAction>| {
+            actions.push(TimedAction { time, action, pressed: true });
+            actions.push(TimedAction { time: time + Duration::from_millis(40), action, pressed: false });
+            time += Duration::from_millis(80);
+        };
+        for piece in 0..120i32 {
+            for _ in 0..piece % 4 {
+                press(Action::Rotate, &mut actions);
+            }
+            let shift = piece % 9 - 4;
+            let direction = if shift < 0 { Action::MoveLeft } else { Action::MoveRight };
+            for _ in 0..shift.abs() {
+                press(direction, &mut actions);
+            }
+            if piece % 5 == 0 {
+                press(Action::SoftDrop, &mut actions);
+            }
+            if piece % 7 == 3 {
+                press(Action::Hold, &mut actions);
+            }
+            press(Action::HardDrop, &mut actions);
+        }
+        actions
+    }
+
+    //➔ Advances `engine` through `actions` in frames of `frame`, until the game is over or `end`
+    fn play(engine: &mut Engine, actions: Vec<TimedAction>, handling: &Handling, frame: Duration, end: Duration) {
+        let mut input = ReplayInput::new(actions);
+        let mut now = Duration::ZERO;
+        while now < end && !engine.topped_out && !engine.finished {
+            now += frame;
+            let actions = input.poll(now);
+            engine.advance(&actions, now, handling);
+        }
+    }
+
+    #[test]
+    fn replay_reloaded_from_its_text_ends_on_the_same_board() {
+        let handling = Handling { das: Duration::from_millis(120), arr: Duration::from_millis(20), sdf: 10 };
+        let end = Duration::from_secs(120);
+        for mode in [GameMode::Marathon, GameMode::Master, GameMode::Dig] {
+            let setup = GameSetup { mode, seed: Some(2024), ..GameSetup::default() };
+            let mut engine = start_game(&setup, setup.seed);
+            play(&mut engine, played_actions(), &handling, Duration::from_millis(16), end);
+            let replay = recorded_replay(&engine, &setup, handling).expect("the game should be recorded");
+            let replay = Replay::parse(&replay.text()).expect("the replay should load back");
+
+            //➔ played back in frames of another length, as another machine would
+            let mut watched = start_game(&replay.setup, replay.setup.seed);
+            play(&mut watched, replay.actions, &replay.handling, Duration::from_millis(7), engine.time);
+            assert!(engine.tetris.pieces_placed() >= 4, "{:?} placed {} pieces", mode, engine.tetris.pieces_placed());
+            assert_eq!(watched.tetris.game_map, engine.tetris.game_map, "{:?}", mode);
+            assert_eq!(watched.tetris.score, engine.tetris.score, "{:?}", mode);
+            assert_eq!(watched.tetris.num_lines, engine.tetris.num_lines, "{:?}", mode);
+            assert_eq!(watched.topped_out, engine.topped_out, "{:?}", mode);
+        }
+    }
+}
@@ -0,0 +1,154 @@
+//➔ Game controllers through SDL's GameController API.
+//
+// Every connected controller is opened so its events reach the event pump, and
+// controllers plugged in or out while the game runs are picked up again. The
+// analog sticks are turned into d-pad presses once they leave the deadzone, so
+// the rest of the game only ever deals with buttons.
+
+use sdl2::controller::{Axis, Button, GameController};
+use sdl2::event::Event;
+use sdl2::keyboard::{Keycode, Mod};
+use sdl2::GameControllerSubsystem;
+
+//➔ An axis reports values from -32768 to 32767
+const AXIS_MAX: i32 = 32767;
+
+struct Pad {
+    controller: GameController,
+    //➔ identifies the controller model in the settings file
+    guid: String,
+    //➔ d-pad direction currently held by the left stick on each axis
+    stick_x: Option<Button>,
+    stick_y: Option<Button>,
+}
+
+pub struct Gamepads {
+    subsystem: GameControllerSubsystem,
+    pads: Vec<Pad>,
+}
+
+impl Gamepads {
+    pub fn new(subsystem: GameControllerSubsystem) -> Gamepads {
+        let mut gamepads = Gamepads {
+            subsystem,
+            pads: Vec::new(),
+        };
+        gamepads.refresh();
+        gamepads
+    }
+
+    //➔ Opens the controllers connected since the last call and forgets the unplugged ones
+    pub fn refresh(&mut self) {
+        self.pads.retain(|pad| pad.controller.attached());
+        let count = match self.subsystem.num_joysticks() {
+            Ok(count) => count,
+            Err(e) => {
+                println!("Could not list the controllers: {}", e);
+                return;
+            },
+        };
+        for index in 0..count {
+            if !self.subsystem.is_game_controller(index) {
+                continue;
+            }
+            let controller = match self.subsystem.open(index) {
+                Ok(controller) => controller,
+                Err(e) => {
+                    println!("Could not open controller {}: {}", index, e);
+                    continue;
+                },
+            };
+            //➔ SDL hands out the same controller again when it is already open
+            if self.pads.iter().any(|pad| pad.controller.instance_id() == controller.instance_id()) {
+                continue;
+            }
+            //➔ the mapping starts with the GUID of the controller, followed by its name
+            let guid = controller.mapping().split(',').next().unwrap_or_default().to_string();
+            println!("Controller connected: {} ({})", controller.name(), guid);
+            self.pads.push(Pad {
+                controller,
+                guid,
+                stick_x: None,
+                stick_y: None,
+            });
+        }
+    }
+
+    //➔ GUID of the controller with the joystick id `which`, as found in controller events
+    pub fn guid(&self, which: u32) -> Option<&str> {
+        self.pads.iter()
+            .find(|pad| pad.controller.instance_id() == which)
+            .map(|pad| pad.guid.as_str())
+    }
+
+    //➔ Player the controller with the joystick id `which` plays as in versus: the first
+    //   controller connected is the first player's
+    pub fn player(&self, which: u32) -> Option<usize> {
+        self.pads.iter().position(|pad| pad.controller.instance_id() == which)
+    }
+
+    //➔ Turns a left stick motion into d-pad presses (true) and releases (false).
+    //   `deadzone` is the share of the stick travel that is ignored, in percent
+    pub fn axis_motion(&mut self, which: u32, axis: Axis, value: i16, deadzone: u32) -> Vec<(Button, bool)> {
+        let pad = match self.pads.iter_mut().find(|pad| pad.controller.instance_id() == which) {
+            Some(pad) => pad,
+            None => return Vec::new(),
+        };
+        let (held, negative, positive) = match axis {
+            Axis::LeftX => (&mut pad.stick_x, Button::DPadLeft, Button::DPadRight),
+            Axis::LeftY => (&mut pad.stick_y, Button::DPadUp, Button::DPadDown),
+            _ => return Vec::new(),
+        };
+        let threshold = AXIS_MAX * deadzone as i32 / 100;
+        let direction = if (value as i32) < -threshold {
+            Some(negative)
+        } else if value as i32 > threshold {
+            Some(positive)
+        } else {
+            None
+        };
+        if direction == *held {
+            return Vec::new();
+        }
+        let mut changes = Vec::new();
+        if let Some(button) = *held {
+            changes.push((button, false));
+        }
+        if let Some(button) = direction {
+            changes.push((button, true));
+        }
+        *held = direction;
+        changes
+    }
+}
+
+//➔ Key a controller button stands for in the menus
+fn menu_key(button: Button) -> Option<Keycode> {
+    match button {
+        Button::DPadUp => Some(Keycode::Up),
+        Button::DPadDown => Some(Keycode::Down),
+        Button::DPadLeft => Some(Keycode::Left),
+        Button::DPadRight => Some(Keycode::Right),
+        Button::A | Button::Start => Some(Keycode::Return),
+        Button::B | Button::Back => Some(Keycode::Escape),
+        _ => None,
+    }
+}
+
+//➔ Lets the menus be driven by a controller: button presses come out as the matching key press
+pub fn as_menu_event(event: Event) -> Event {
+    match event {
+        Event::ControllerButtonDown {timestamp, button, ..} => match menu_key(button) {
+            Some(keycode) => Event::KeyDown {
+                timestamp,
+                window_id: 0,
+                keycode: Some(keycode),
+                scancode: None,
+                keymod: Mod::NOMOD,
+                repeat: false,
+            },
+            None => event,
+        },
+        _ => event,
+    }
+}
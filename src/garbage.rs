@@ -0,0 +1,90 @@
+//➔ Garbage rows pushed into the board from the bottom.
+//
+// Every garbage row is full except for one hole. The hole stays in the same
+// column from one row to the next unless the messiness says otherwise, so a
+// clean stack can be dug through with a few vertical pieces while a messy one
+// needs a different piece for almost every row.
+//
+// Dig races keep a few rows at the bottom until the race is over, survival
+// games push a new row in on a timer.
+
+use crate::randomizer::Randomizer;
+use crate::GARBAGE;
+
+use std::time::Duration;
+
+pub struct GarbageGenerator {
+    randomizer: Randomizer,
+    //➔ column of the hole in the last row, none before the first one
+    hole: Option<usize>,
+    //➔ chance in percent that the hole moves to another column on the next row
+    messiness: u32,
+}
+
+impl GarbageGenerator {
+    //➔ The same seed always gives the same rows
+    pub fn new(seed: u64, messiness: u32) -> GarbageGenerator {
+        GarbageGenerator {
+            randomizer: Randomizer::from_seed(seed),
+            hole: None,
+            messiness,
+        }
+    }
+
+    //➔ Lets the hole of the next row land in any column, as if it were the first row
+    pub fn new_hole(&mut self) {
+        self.hole = None;
+    }
+
+    pub fn next_row(&mut self, width: usize) -> Vec<u8> {
+        let hole = match self.hole {
+            None => self.randomizer.next_below(width as u32) as usize,
+            //➔ a moved hole always ends up in another column
+            Some(hole) if self.randomizer.next_below(100) < self.messiness => {
+                (hole + 1 + self.randomizer.next_below(width as u32 - 1) as usize) % width
+            },
+            Some(hole) => hole,
+        };
+        self.hole = Some(hole);
+        let mut row = vec![GARBAGE; width];
+        row[hole] = 0;
+        row
+    }
+}
+
+//➔ Garbage of the dig mode: the bottom of the board is kept filled with a few rows
+//   until all the rows of the race have come in
+pub struct Dig {
+    pub generator: GarbageGenerator,
+    //➔ rows still to be pushed into the board
+    pub rows_left: u32,
+}
+
+//➔ Time between the first two rising rows, every row after that comes a bit sooner
+const RISE_START: Duration = Duration::from_millis(8000);
+const RISE_SPEEDUP: Duration = Duration::from_millis(250);
+const RISE_FASTEST: Duration = Duration::from_millis(1500);
+
+//➔ Garbage of the survival mode: rows rise from the bottom on a timer that shortens with every row
+pub struct RisingGarbage {
+    pub generator: GarbageGenerator,
+    //➔ engine time the next row comes in at
+    pub next_time: Duration,
+    //➔ rows that came in so far
+    pub rows: u32,
+}
+
+impl RisingGarbage {
+    pub fn new(generator: GarbageGenerator) -> RisingGarbage {
+        RisingGarbage {
+            generator,
+            next_time: RISE_START,
+            rows: 0,
+        }
+    }
+
+    //➔ Time until the row after the current one
+    pub fn interval(&self) -> Duration {
+        RISE_START.saturating_sub(RISE_SPEEDUP * self.rows).max(RISE_FASTEST)
+    }
+}
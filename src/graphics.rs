@@ -0,0 +1,281 @@
+//➔ Window, canvas and the textures used to draw the board.
+
+use crate::ruleset::BlockColors;
+use crate::settings::{Settings, Theme};
+use crate::{Tetrimino, TETRIS_HEIGHT};
+
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, Canvas, Texture, TextureCreator};
+use sdl2::video::{FullscreenType, Window, WindowContext};
+
+//➔ Color of every block value in the game map, block value 1 being the first entry
+//   and the last one being garbage
+pub const BLOCK_COLORS: [(u8, u8, u8); 8] = [(255, 69, 69), (255, 220, 69), (237, 150, 37), (171, 99, 237),
+                                             (77, 149, 239), (39, 218, 225), (45, 216, 47), (128, 128, 128)];
+
+fn create_texture_rect<'a>(canvas: &mut Canvas<Window>,
+    texture_creator: &'a TextureCreator<WindowContext>,
+     r: u8, g: u8, b: u8,
+     width: u32, height: u32) -> Option<Texture<'a>> {
+
+        if let Ok(mut texture) = texture_creator.create_texture_target(None, width, height) {
+            canvas.with_texture_canvas(&mut texture, |texture| {
+                texture.set_draw_color(Color::RGB(r, g, b));
+                texture.clear();
+            }).expect("Failed to color a texture!");
+            //➔ so blocks can be drawn see-through
+            texture.set_blend_mode(BlendMode::Blend);
+            Some(texture)
+        }else {
+            None
+        }
+}
+
+//➔ The grid and border textures, whose colors depend on the theme
+fn create_board_textures<'a>(canvas: &mut Canvas<Window>,
+    texture_creator: &'a TextureCreator<WindowContext>, theme: Theme) -> (Texture<'a>, Texture<'a>) {
+
+    let (r, g, b) = theme.grid().rgb();
+    let grid = create_texture_rect(canvas,
+        texture_creator, r, g, b,
+        TETRIS_HEIGHT as u32 * 10,
+        TETRIS_HEIGHT as u32 * 16).expect("failed to create the grid texture");
+
+    let (r, g, b) = theme.border().rgb();
+    let border = create_texture_rect(canvas,
+         texture_creator, r, g, b,
+         TETRIS_HEIGHT as u32 * 10 + 20,
+         TETRIS_HEIGHT as u32 * 16 + 20).expect("failed to create the border texture");
+    (grid, border)
+}
+
+pub struct Graphics<'a> {
+    pub canvas: Canvas<Window>,
+    pub width: u32,
+    pub height: u32,
+    pub grid_x: i32,
+    pub grid_y: i32,
+    theme: Theme,
+    texture_creator: &'a TextureCreator<WindowContext>,
+    grid: Texture<'a>,
+    border: Texture<'a>,
+    textures: [Texture<'a>; 8],
+    //➔ colors replacing the textures of the blocks, for the rulesets whose colors change with the level
+    palette: Option<[BlockColors; 8]>,
+    //➔ blocks are drawn this many times their usual size, for big games
+    scale: u32,
+    //➔ the board is drawn with its top row at the bottom
+    upside_down: bool,
+}
+
+impl<'a> Graphics<'a> {
+    pub fn new(mut canvas: Canvas<Window>, texture_creator: &'a TextureCreator<WindowContext>, theme: Theme) -> Graphics<'a> {
+        canvas.set_blend_mode(BlendMode::Blend);
+        let (grid, border) = create_board_textures(&mut canvas, texture_creator, theme);
+
+        //fixed-length array
+        let textures = BLOCK_COLORS.map(|(r, g, b)| {
+            create_texture_rect(&mut canvas,
+                texture_creator,
+                r, g, b,
+                TETRIS_HEIGHT as u32,
+                TETRIS_HEIGHT as u32).unwrap()
+        });
+
+        let mut graphics = Graphics {
+            canvas,
+            width: 0,
+            height: 0,
+            grid_x: 0,
+            grid_y: 0,
+            theme,
+            texture_creator,
+            grid,
+            border,
+            textures,
+            palette: None,
+            scale: 1,
+            upside_down: false,
+        };
+        graphics.update_layout();
+        graphics
+    }
+
+    //➔ Centers the board in the window, to be called whenever the window size changes
+    fn update_layout(&mut self) {
+        let (width, height) = self.canvas.output_size().unwrap_or_else(|_| self.canvas.window().size());
+        self.width = width;
+        self.height = height;
+        self.grid_x = (width as i32 - TETRIS_HEIGHT as i32 * 10) / 2;
+        self.grid_y = (height as i32 - TETRIS_HEIGHT as i32 * 16) / 2;
+    }
+
+    //➔ Moves the board so its left edge is at `x`, for drawing boards side by side.
+    //   `None` puts it back in the middle of the window
+    pub fn place_board(&mut self, x: Option<i32>) {
+        self.grid_x = x.unwrap_or((self.width as i32 - TETRIS_HEIGHT as i32 * 10) / 2);
+    }
+
+    //➔ Applies the display part of the settings: window size, fullscreen and theme
+    pub fn apply_settings(&mut self, settings: &Settings) -> Result<(), String> {
+        let window = self.canvas.window_mut();
+        window.set_fullscreen(if settings.fullscreen { FullscreenType::Desktop } else { FullscreenType::Off })?;
+        if !settings.fullscreen {
+            window.set_size(settings.window_width, settings.window_height).map_err(|e| e.to_string())?;
+        }
+        self.update_layout();
+        if settings.theme != self.theme {
+            let (grid, border) = create_board_textures(&mut self.canvas, self.texture_creator, settings.theme);
+            self.grid = grid;
+            self.border = border;
+            self.theme = settings.theme;
+        }
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        //➔ set the background of the canvas
+        self.canvas.set_draw_color(self.theme.background());
+        self.canvas.clear();
+    }
+
+    //➔ Draws the border and, unless the board has to stay hidden, the empty grid inside it
+    pub fn draw_board(&mut self, show_grid: bool) {
+        //➔ draw the border
+        self.canvas.copy(&self.border, None, Rect::new(
+            self.grid_x - 10,
+            self.grid_y - 10,
+            TETRIS_HEIGHT as u32 * 10 + 20,
+            TETRIS_HEIGHT as u32 * 16 + 20)).expect("failed to render the border");
+
+        if show_grid {
+            //➔ draw the grid
+            self.canvas.copy(&self.grid, None, Rect::new(
+                self.grid_x,
+                self.grid_y,
+                TETRIS_HEIGHT as u32 * 10,
+                TETRIS_HEIGHT as u32 * 16)).expect("failed to render the grid");
+        }
+    }
+
+    //➔ Colors the blocks get instead of their textures, `None` goes back to the textures
+    pub fn set_palette(&mut self, palette: Option<[BlockColors; 8]>) {
+        self.palette = palette;
+    }
+
+    //➔ How blocks are laid out on the board: `scale` times their usual size, and with the
+    //   top row at the bottom when `upside_down` is set
+    pub fn set_view(&mut self, scale: u32, upside_down: bool) {
+        self.scale = scale;
+        self.upside_down = upside_down;
+    }
+
+    fn cell_size(&self) -> u32 {
+        TETRIS_HEIGHT as u32 * self.scale
+    }
+
+    //➔ Top left corner of the cell at `row` and `col` of the game map
+    fn cell_position(&self, row: isize, col: isize) -> (i32, i32) {
+        let size = self.cell_size() as i32;
+        let x = self.grid_x + col as i32 * size;
+        let y = if self.upside_down {
+            self.grid_y + TETRIS_HEIGHT as i32 * 16 - (row as i32 + 1) * size
+        } else {
+            self.grid_y + row as i32 * size
+        };
+        (x, y)
+    }
+
+    //➔ Draws a block of the game map value `value` in the cell at `row` and `col`,
+    //   `alpha` going from 0 for not shown at all to 255 for fully shown
+    fn draw_block(&mut self, value: u8, row: isize, col: isize, alpha: u8) -> Result<(), String> {
+        if alpha == 0 {
+            return Ok(());
+        }
+        let (x, y) = self.cell_position(row, col);
+        let size = self.cell_size();
+        let rect = Rect::new(x, y, size, size);
+        match self.palette {
+            Some(palette) => {
+                let ((r, g, b), (fill_r, fill_g, fill_b)) = palette[value as usize - 1];
+                self.canvas.set_draw_color(Color::RGBA(r, g, b, alpha));
+                self.canvas.fill_rect(rect)?;
+                self.canvas.set_draw_color(Color::RGBA(fill_r, fill_g, fill_b, alpha));
+                let inset = 4 * self.scale;
+                self.canvas.fill_rect(Rect::new(x + inset as i32, y + inset as i32, size - 2 * inset, size - 2 * inset))
+            },
+            None => {
+                let texture = &mut self.textures[value as usize - 1];
+                texture.set_alpha_mod(alpha);
+                let result = self.canvas.copy(texture, None, rect);
+                texture.set_alpha_mod(255);
+                result
+            },
+        }
+    }
+
+    pub fn draw_piece(&mut self, piece: &Tetrimino) {
+        //➔ Draw the current tetrimino
+        for (row_num, row) in piece.states[piece.current_state].iter().enumerate() {
+            for (col_num, &col_val) in row.iter().enumerate() {
+                if col_val != 0 {
+                    self.draw_block(col_val, (piece.y + row_num) as isize, piece.x + col_num as isize, 255)
+                        .expect("failed to render tetrimino");
+                }
+            }
+        }
+    }
+
+    //➔ Outline of where the tetrimino would land, drawn at row `ghost_y`
+    pub fn draw_ghost(&mut self, piece: &Tetrimino, ghost_y: usize) -> Result<(), String> {
+        for (row_num, row) in piece.states[piece.current_state].iter().enumerate() {
+            for (col_num, &col_val) in row.iter().enumerate() {
+                if col_val != 0 {
+                    let (r, g, b) = BLOCK_COLORS[col_val as usize - 1];
+                    self.canvas.set_draw_color(Color::RGB(r, g, b));
+                    let (x, y) = self.cell_position((ghost_y + row_num) as isize, piece.x + col_num as isize);
+                    let size = self.cell_size();
+                    for inset in 0..3 {
+                        self.canvas.draw_rect(Rect::new(x + inset, y + inset, size - 2 * inset as u32, size - 2 * inset as u32))?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn draw_map(&mut self, game_map: &[Vec<u8>]) {
+        self.draw_faded_map(game_map, |_, _| 255);
+    }
+
+    //➔ Draws the game map with every block at the opacity `alpha` gives for its row and column
+    pub fn draw_faded_map(&mut self, game_map: &[Vec<u8>], alpha: impl Fn(usize, usize) -> u8) {
+        //➔ Draw the game map
+        for (row_num, row) in game_map.iter().enumerate() {
+            for (col_num, col_val) in row.iter().enumerate() {
+                if *col_val == 0 {
+                    continue;
+                }
+                self.draw_block(*col_val, row_num as isize, col_num as isize, alpha(row_num, col_num))
+                    .expect("failed to render game map into window");
+            }
+        }
+    }
+
+    //➔ White outline around the cell at `row` and `col`, for blocks that just locked
+    pub fn draw_outline(&mut self, row: usize, col: usize) -> Result<(), String> {
+        self.canvas.set_draw_color(Color::RGB(255, 255, 255));
+        let (x, y) = self.cell_position(row as isize, col as isize);
+        let size = self.cell_size();
+        for inset in 0..2 {
+            self.canvas.draw_rect(Rect::new(x + inset, y + inset, size - 2 * inset as u32, size - 2 * inset as u32))?;
+        }
+        Ok(())
+    }
+
+    pub fn present(&mut self) {
+        //➔ present the window
+        self.canvas.present();
+    }
+}
@@ -0,0 +1,85 @@
+//➔ Delayed auto shift: holding left or right moves the piece once, waits DAS,
+//  then keeps moving it every ARR until the key is released.
+
+use crate::settings::Settings;
+
+use std::time::Duration;
+
+//➔ Moving this many cells at once takes a piece from one wall to the other
+const MAX_SHIFT: i32 = 10;
+
+#[derive(Debug, Default)]
+pub struct AutoShift {
+    left: bool,
+    right: bool,
+    //➔ -1 for left, 1 for right, 0 when neither is held
+    direction: i32,
+    pressed_at: Duration,
+    //➔ engine time of the next automatic move, set once DAS has run out
+    next_shift: Option<Duration>,
+}
+
+impl AutoShift {
+    pub fn press(&mut self, direction: i32, now: Duration) {
+        if direction < 0 {
+            self.left = true;
+        } else {
+            self.right = true;
+        }
+        self.charge(direction, now);
+    }
+
+    pub fn release(&mut self, direction: i32, now: Duration) {
+        if direction < 0 {
+            self.left = false;
+        } else {
+            self.right = false;
+        }
+        //➔ when both were held, letting go of the newer one goes back to the older one
+        if self.direction == direction {
+            let other = if self.left { -1 } else if self.right { 1 } else { 0 };
+            self.charge(other, now);
+        }
+    }
+
+    fn charge(&mut self, direction: i32, now: Duration) {
+        self.direction = direction;
+        self.pressed_at = now;
+        self.next_shift = None;
+    }
+
+    //➔ Cells the piece should move this frame, negative for left
+    pub fn shifts(&mut self, now: Duration, das: Duration, arr: Duration) -> i32 {
+        if self.direction == 0 || now < self.pressed_at + das {
+            return 0;
+        }
+        let mut next = self.next_shift.unwrap_or(self.pressed_at + das);
+        let mut cells = 0;
+        //➔ with an ARR of zero this runs until the piece has crossed the whole board
+        while now >= next && cells < MAX_SHIFT {
+            cells += 1;
+            next += arr;
+        }
+        self.next_shift = Some(next);
+        cells * self.direction
+    }
+}
+
+//➔ The part of the settings that changes how the game plays, kept with replays so they play back the same
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Handling {
+    pub das: Duration,
+    pub arr: Duration,
+    //➔ soft drop factor: how many times faster than gravity a soft drop falls
+    pub sdf: u32,
+}
+
+impl Handling {
+    pub fn from_settings(settings: &Settings) -> Handling {
+        Handling {
+            das: Duration::from_millis(settings.das as u64),
+            arr: Duration::from_millis(settings.arr as u64),
+            sdf: settings.sdf,
+        }
+    }
+}
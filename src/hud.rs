@@ -0,0 +1,196 @@
+//➔ In-game HUD drawn on both sides of the board.
+
+use crate::font::{draw_text, text_height, text_width};
+use crate::graphics::{Graphics, BLOCK_COLORS};
+use crate::{Tetrimino, Tetris, TETRIS_HEIGHT};
+
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+
+use std::time::Duration;
+
+const LABEL_SCALE: u32 = 2;
+const VALUE_SCALE: u32 = 3;
+//➔ Gap between the board border and the HUD columns
+const MARGIN: i32 = 60;
+const COLUMN_WIDTH: i32 = 240;
+//➔ Blocks of the upcoming pieces are drawn at half size
+const PREVIEW_BLOCK: u32 = TETRIS_HEIGHT as u32 / 2;
+const PREVIEW_WIDTH: i32 = PREVIEW_BLOCK as i32 * 4 + 60;
+
+//➔ Gap between a versus board and its column, and the width of its garbage meter
+const VERSUS_MARGIN: i32 = 8;
+const METER_WIDTH: u32 = 10;
+//➔ Upcoming pieces a versus column has room for
+const VERSUS_PREVIEW: u32 = 3;
+//➔ Width of the column next to a versus board, meter included
+pub const VERSUS_COLUMN: i32 = VERSUS_MARGIN * 2 + METER_WIDTH as i32 + PREVIEW_WIDTH;
+
+//➔ Names of the piece kinds, in the order of `Tetris::piece_counts`
+const PIECE_NAMES: [&str; 7] = ["I", "O", "T", "S", "Z", "J", "L"];
+
+//➔ Something a game mode wants to show besides the usual entries
+pub struct HudEntry {
+    pub label: String,
+    pub value: String,
+    //➔ drawn highlighted, e.g. when falling behind or running out of time
+    pub warning: bool,
+}
+
+//➔ Formats a duration as minutes, seconds and hundredths: 1:05.42
+pub fn format_time(time: Duration) -> String {
+    let centis = time.as_millis() / 10;
+    format!("{}:{:02}.{:02}", centis / 6000, centis / 100 % 60, centis % 100)
+}
+
+//➔ Formats a duration down to the millisecond: 1:05.421
+pub fn format_time_millis(time: Duration) -> String {
+    let millis = time.as_millis();
+    format!("{}:{:02}.{:03}", millis / 60000, millis / 1000 % 60, millis % 1000)
+}
+
+//➔ Formats the difference between a time and a reference one: -0.512, +1.024
+pub fn format_delta(time: Duration, reference: Duration) -> String {
+    if time <= reference {
+        let millis = (reference - time).as_millis();
+        format!("-{}.{:03}", millis / 1000, millis % 1000)
+    } else {
+        let millis = (time - reference).as_millis();
+        format!("+{}.{:03}", millis / 1000, millis % 1000)
+    }
+}
+
+fn pieces_per_second(tetris: &Tetris, elapsed: Duration) -> f64 {
+    if elapsed.as_secs_f64() < 1.0 {
+        return 0.0;
+    }
+    tetris.pieces_placed() as f64 / elapsed.as_secs_f64()
+}
+
+//➔ Draws a small label with its value underneath, returns the y position for the next entry
+fn draw_entry(graphics: &mut Graphics, label: &str, value: &str, x: i32, y: i32) -> Result<i32, String> {
+    draw_colored_entry(graphics, label, value, x, y, Color::RGB(255, 255, 255))
+}
+
+fn draw_colored_entry(graphics: &mut Graphics, label: &str, value: &str, x: i32, y: i32, color: Color) -> Result<i32, String> {
+    draw_text(&mut graphics.canvas, label, x, y, LABEL_SCALE, color)?;
+    let y = y + text_height(LABEL_SCALE) as i32 + 6;
+    draw_text(&mut graphics.canvas, value, x, y, VALUE_SCALE, color)?;
+    Ok(y + text_height(VALUE_SCALE) as i32 * 2)
+}
+
+//➔ Draws a piece of the given kind with half-size blocks, returns the number of rows it takes
+fn draw_small_piece(graphics: &mut Graphics, kind: u8, x: i32, y: i32) -> Result<i32, String> {
+    let piece = Tetrimino::from_kind(kind);
+    let (r, g, b) = BLOCK_COLORS[kind as usize];
+    let mut rows = 0;
+    for (row_num, row) in piece.states[0].iter().enumerate() {
+        for (col_num, &block) in row.iter().enumerate() {
+            if block != 0 {
+                rows = rows.max(row_num as i32 + 1);
+                graphics.canvas.set_draw_color(Color::RGB(r, g, b));
+                graphics.canvas.fill_rect(Rect::new(
+                    x + col_num as i32 * PREVIEW_BLOCK as i32,
+                    y + row_num as i32 * PREVIEW_BLOCK as i32,
+                    PREVIEW_BLOCK - 1,
+                    PREVIEW_BLOCK - 1))?;
+            }
+        }
+    }
+    Ok(rows)
+}
+
+//➔ Draws the `count` upcoming pieces in a column starting at (x, y)
+fn draw_preview(graphics: &mut Graphics, tetris: &Tetris, count: usize, x: i32, y: i32) -> Result<(), String> {
+    draw_text(&mut graphics.canvas, "NEXT", x, y, LABEL_SCALE, Color::RGB(255, 255, 255))?;
+    let mut y = y + text_height(LABEL_SCALE) as i32 * 3;
+    for &kind in tetris.next_kinds.iter().take(count) {
+        let rows = draw_small_piece(graphics, kind, x, y)?;
+        y += (rows + 1) * PREVIEW_BLOCK as i32;
+    }
+    Ok(())
+}
+
+//➔ Draws the held piece, greyed out while it cannot be swapped. Returns the y position for the next entry
+fn draw_hold(graphics: &mut Graphics, tetris: &Tetris, x: i32, y: i32) -> Result<i32, String> {
+    let color = if tetris.hold_used { Color::RGB(128, 128, 128) } else { Color::RGB(255, 255, 255) };
+    draw_text(&mut graphics.canvas, "HOLD", x, y, LABEL_SCALE, color)?;
+    let y = y + text_height(LABEL_SCALE) as i32 * 3;
+    if let Some(kind) = tetris.held_kind {
+        draw_small_piece(graphics, kind, x, y)?;
+    }
+    //➔ room for the tallest piece, so the entries below do not move around
+    Ok(y + 5 * PREVIEW_BLOCK as i32)
+}
+
+//➔ `elapsed` is the time shown for the game, which does not count pauses,
+//   `preview` the number of upcoming pieces to show and `extra` the entries of the game mode
+pub fn draw_hud(graphics: &mut Graphics, tetris: &Tetris, elapsed: Duration, preview: u32, extra: &[HudEntry]) -> Result<(), String> {
+    //➔ Left column: the held piece, score and progress
+    let x = graphics.grid_x - 10 - MARGIN - COLUMN_WIDTH;
+    let mut y = graphics.grid_y;
+    if tetris.has_hold() {
+        y = draw_hold(graphics, tetris, x, y)?;
+    }
+    y = draw_entry(graphics, "SCORE", &tetris.score.to_string(), x, y)?;
+    y = draw_entry(graphics, "LEVEL", &tetris.current_level.to_string(), x, y)?;
+    y = draw_entry(graphics, "LINES", &tetris.num_lines.to_string(), x, y)?;
+    y = draw_entry(graphics, "TIME", &format_time(elapsed), x, y)?;
+    y = draw_entry(graphics, "PIECES", &tetris.pieces_placed().to_string(), x, y)?;
+    draw_entry(graphics, "PPS", &format!("{:.2}", pieces_per_second(tetris, elapsed)), x, y)?;
+
+    //➔ Right columns: the upcoming pieces, then how many pieces of each kind were placed
+    let x = graphics.grid_x + TETRIS_HEIGHT as i32 * 10 + 10 + MARGIN;
+    draw_preview(graphics, tetris, tetris.ruleset.preview(preview) as usize, x, graphics.grid_y)?;
+
+    let x = x + PREVIEW_WIDTH;
+    let mut y = graphics.grid_y;
+    draw_text(&mut graphics.canvas, "STATISTICS", x, y, LABEL_SCALE, Color::RGB(255, 255, 255))?;
+    y += text_height(LABEL_SCALE) as i32 * 3;
+    for (kind, name) in PIECE_NAMES.iter().enumerate() {
+        let (r, g, b) = BLOCK_COLORS[kind];
+        let line = format!("{}  {:>4}", name, tetris.piece_counts[kind]);
+        //➔ a dark backdrop keeps the lighter piece colors readable on the background
+        graphics.canvas.set_draw_color(Color::RGB(0, 0, 0));
+        graphics.canvas.fill_rect(Rect::new(x - 6, y - 6,
+            text_width(&line, VALUE_SCALE) + 12, text_height(VALUE_SCALE) + 12))?;
+        draw_text(&mut graphics.canvas, &line, x, y, VALUE_SCALE, Color::RGB(r, g, b))?;
+        y += text_height(VALUE_SCALE) as i32 * 2;
+    }
+
+    //➔ The entries of the game mode go under the statistics
+    y += text_height(VALUE_SCALE) as i32;
+    for entry in extra {
+        let color = if entry.warning { Color::RGB(255, 220, 69) } else { Color::RGB(255, 255, 255) };
+        y = draw_colored_entry(graphics, &entry.label, &entry.value, x, y, color)?;
+    }
+    Ok(())
+}
+
+//➔ The column next to a board of a versus game: the garbage meter along the board, then
+//   the name of the player, the held piece, the upcoming pieces and the lines sent
+pub fn draw_versus_hud(graphics: &mut Graphics, tetris: &Tetris, player: &str, pending: u32, sent: u32,
+    preview: u32) -> Result<(), String> {
+    let x = graphics.grid_x + TETRIS_HEIGHT as i32 * 10 + 10 + VERSUS_MARGIN;
+    //➔ the meter fills up from the bottom of the board, a row of garbage for a row of the board
+    let rows = pending.min(16) as i32;
+    let bottom = graphics.grid_y + TETRIS_HEIGHT as i32 * 16;
+    graphics.canvas.set_draw_color(Color::RGB(255, 69, 69));
+    if rows > 0 {
+        graphics.canvas.fill_rect(Rect::new(x, bottom - rows * TETRIS_HEIGHT as i32,
+            METER_WIDTH, rows as u32 * TETRIS_HEIGHT as u32))?;
+    }
+
+    let x = x + METER_WIDTH as i32 + VERSUS_MARGIN;
+    let mut y = graphics.grid_y;
+    draw_text(&mut graphics.canvas, player, x, y, LABEL_SCALE, Color::RGB(255, 255, 255))?;
+    y += text_height(LABEL_SCALE) as i32 * 3;
+    if tetris.has_hold() {
+        y = draw_hold(graphics, tetris, x, y)?;
+    }
+    draw_preview(graphics, tetris, tetris.ruleset.preview(preview).min(VERSUS_PREVIEW) as usize, x, y)?;
+    let y = bottom - (text_height(LABEL_SCALE) as i32 + 6 + text_height(VALUE_SCALE) as i32 * 2) * 2;
+    let y = draw_entry(graphics, "SENT", &sent.to_string(), x, y)?;
+    draw_entry(graphics, "LINES", &tetris.num_lines.to_string(), x, y)?;
+    Ok(())
+}
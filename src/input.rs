@@ -0,0 +1,137 @@
+//➔ Where the actions of a player come from.
+//
+// The engine never looks at devices: keyboard and controllers, a recorded
+// replay, and later network peers or bots all hand it the same stream of
+// timestamped actions.
+
+use crate::gamepad::Gamepads;
+use crate::settings::{Action, Settings};
+
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+//➔ An action pressed (or released) at some engine time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimedAction {
+    pub time: Duration,
+    pub action: Action,
+    pub pressed: bool,
+}
+
+pub trait InputSource {
+    //➔ Actions that happened up to the engine time `now`, oldest first
+    fn poll(&mut self, now: Duration) -> Vec<TimedAction>;
+}
+
+//➔ Keyboard and controllers, through the bindings of the settings
+#[derive(Default)]
+pub struct DeviceInput {
+    queue: Vec<TimedAction>,
+}
+
+impl DeviceInput {
+    fn push(&mut self, action: Option<Action>, pressed: bool, now: Duration) {
+        if let Some(action) = action {
+            self.queue.push(TimedAction { time: now, action, pressed });
+        }
+    }
+
+    //➔ Turns a key or button event into an action happening at `now`, other events are ignored
+    pub fn handle_event(&mut self, event: &Event, settings: &Settings, gamepads: &mut Gamepads, now: Duration) {
+        match *event {
+            //➔ Key repeats are ignored: holding a key is handled by DAS and the soft drop factor
+            Event::KeyDown {keycode: Some(keycode), repeat: false, ..} => {
+                self.push(settings.action_for_key(keycode), true, now);
+            },
+            Event::KeyUp {keycode: Some(keycode), ..} => {
+                self.push(settings.action_for_key(keycode), false, now);
+            },
+            Event::ControllerButtonDown {which, button, ..} => {
+                self.push(settings.action_for_button(gamepads.guid(which), button), true, now);
+            },
+            Event::ControllerButtonUp {which, button, ..} => {
+                self.push(settings.action_for_button(gamepads.guid(which), button), false, now);
+            },
+            Event::ControllerAxisMotion {which, axis, value, ..} => {
+                for (button, pressed) in gamepads.axis_motion(which, axis, value, settings.deadzone) {
+                    self.push(settings.action_for_button(gamepads.guid(which), button), pressed, now);
+                }
+            },
+            _ => {},
+        }
+    }
+}
+
+impl InputSource for DeviceInput {
+    fn poll(&mut self, _now: Duration) -> Vec<TimedAction> {
+        std::mem::take(&mut self.queue)
+    }
+}
+
+//➔ Keyboard and controllers of a local versus game, sorted out by player: each player has
+//   keys of their own and the controllers go to the players in the order they were connected
+#[derive(Default)]
+pub struct VersusInput {
+    players: [DeviceInput; 2],
+}
+
+impl VersusInput {
+    pub fn handle_event(&mut self, event: &Event, settings: &Settings, gamepads: &mut Gamepads, now: Duration) {
+        match *event {
+            Event::KeyDown {keycode: Some(keycode), repeat: false, ..} => {
+                let (player, action) = key_player(settings, keycode);
+                self.players[player].push(action, true, now);
+            },
+            Event::KeyUp {keycode: Some(keycode), ..} => {
+                let (player, action) = key_player(settings, keycode);
+                self.players[player].push(action, false, now);
+            },
+            Event::ControllerButtonDown {which, ..} | Event::ControllerButtonUp {which, ..} |
+            Event::ControllerAxisMotion {which, ..} => {
+                if let Some(input) = gamepads.player(which).and_then(|player| self.players.get_mut(player)) {
+                    input.handle_event(event, settings, gamepads, now);
+                }
+            },
+            _ => {},
+        }
+    }
+
+    //➔ Actions of `player` up to the engine time `now`, oldest first
+    pub fn poll(&mut self, player: usize, now: Duration) -> Vec<TimedAction> {
+        self.players[player].poll(now)
+    }
+}
+
+//➔ The player a key belongs to and the action it stands for, the first player's keys going first
+fn key_player(settings: &Settings, keycode: Keycode) -> (usize, Option<Action>) {
+    match settings.action_for_key(keycode) {
+        Some(action) => (0, Some(action)),
+        None => (1, settings.versus_action_for_key(keycode)),
+    }
+}
+
+//➔ Plays back the actions of a recorded game at the time they happened
+pub struct ReplayInput {
+    actions: VecDeque<TimedAction>,
+}
+
+impl ReplayInput {
+    pub fn new(actions: Vec<TimedAction>) -> ReplayInput {
+        ReplayInput {
+            actions: actions.into(),
+        }
+    }
+}
+
+impl InputSource for ReplayInput {
+    fn poll(&mut self, now: Duration) -> Vec<TimedAction> {
+        let mut actions = Vec::new();
+        while self.actions.front().is_some_and(|action| action.time <= now) {
+            actions.extend(self.actions.pop_front());
+        }
+        actions
+    }
+}
@@ -0,0 +1,55 @@
+//➔ Invisible mode: the stack has to be kept in mind.
+//
+// Locked blocks stay on the board for the fade time and then fade out, or they
+// vanish as soon as they lock when there is no fade time. Every block flashes an
+// outline as it locks so the player sees where the piece went, and the whole
+// board is shown once the game is over.
+
+use crate::Tetris;
+
+use std::time::Duration;
+
+//➔ How long the outline of a block that just locked is shown
+pub const FLASH: Duration = Duration::from_millis(200);
+//➔ How long fading blocks take to go from fully shown to gone
+const FADE_OUT: Duration = Duration::from_millis(1000);
+
+pub struct Invisible {
+    //➔ how long a locked block stays fully shown, zero for blocks that vanish on lock
+    pub fade: Duration,
+}
+
+impl Invisible {
+    pub fn new(fade: Duration) -> Invisible {
+        Invisible { fade }
+    }
+
+    //➔ Opacity of a block locked at `locked_at`, at engine time `now`. Blocks that vanish
+    //   on lock are only shown while they flash
+    pub fn alpha(&self, locked_at: Duration, now: Duration) -> u8 {
+        let age = now.saturating_sub(locked_at);
+        if self.fade.is_zero() {
+            return if age < FLASH { 255 } else { 0 };
+        }
+        if age < self.fade {
+            return 255;
+        }
+        let fading = age - self.fade;
+        if fading >= FADE_OUT {
+            return 0;
+        }
+        (255 - 255 * fading.as_millis() / FADE_OUT.as_millis()) as u8
+    }
+
+    //➔ Opacity of every block of the board, in the shape of the game map
+    pub fn alphas(&self, tetris: &Tetris, now: Duration) -> Vec<Vec<u8>> {
+        tetris.lock_times.iter()
+            .map(|row| row.iter().map(|&locked_at| self.alpha(locked_at, now)).collect())
+            .collect()
+    }
+}
+
+//➔ Whether a block locked at `locked_at` still shows its outline at `now`
+pub fn flashing(locked_at: Duration, now: Duration) -> bool {
+    now.saturating_sub(locked_at) < FLASH
+}
@@ -1,24 +1,63 @@
-use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
-use sdl2::pixels::Color;
-use sdl2::rect::Rect;
-use sdl2::render::{Canvas, Texture, TextureCreator};
-use sdl2::video::{Window, WindowContext};
-
+mod attack;
+mod clock;
+mod daily;
+mod engine;
+mod font;
+mod game;
+mod gamepad;
+mod garbage;
+mod graphics;
+mod handling;
+mod hud;
+mod input;
+mod invisible;
+mod master;
+mod menu;
+mod mission;
+mod mode;
+mod netplay;
+mod puzzle;
+mod randomizer;
+mod records;
+mod replay;
+mod ruleset;
+mod save;
+mod scoring;
+mod screens;
+mod settings;
+mod variant;
+mod versus;
+mod zen;
+
+use game::{GameEnd, GameSetup};
+use gamepad::Gamepads;
+use graphics::Graphics;
+use randomizer::Randomizer;
+use replay::Replay;
+use ruleset::Ruleset;
+use scoring::Scoring;
+use screens::TitleAction;
+use sdl2::render::TextureCreator;
+use settings::Settings;
+
+use std::collections::VecDeque;
 use std::fs::File;
 use std::io::{self, Read, Write};
-use std::thread;
-use std::time::{Duration, SystemTime};
 use std::error::Error;
+use std::time::Duration;
 
 const TETRIS_HEIGHT : usize = 32;
+//➔ Block value of garbage cells, the values before it are the colors of the piece kinds
+const GARBAGE: u8 = 8;
 const HIGHSCORE_FILE: &'static str = "scores.txt";
-const LEVEL_LINES: [u32; 10] = [20, 40, 60, 80, 100, 120, 140, 160, 180, 200];
-
-const LEVEL_TIMES: [u32;10] = [1000, 850, 750, 650, 600, 550, 500, 400, 350, 300];
-
 const NUM_HIGHSCORES: usize = 5;
 
+//➔ Upcoming pieces known in advance, the preview shows some of them
+const NEXT_PIECES: usize = 5;
+//➔ Columns and rows of the board, big games have half as many of each
+const BOARD_WIDTH: usize = 10;
+const BOARD_HEIGHT: usize = 16;
+
 type Piece = Vec<Vec<u8>>;
 type States = Vec<Piece>;
 
@@ -26,7 +65,7 @@ trait TetriminoGenerator{
     fn new() -> Tetrimino;
 }
 
-#[derive(Debug,)]
+#[derive(Debug, Clone)]
 struct Tetrimino {
     states: States,
     current_state: usize,
@@ -204,14 +243,12 @@ impl TetriminoGenerator for TetriminoT {
 }
 
 impl Tetrimino {
-    fn create_at_random() -> Tetrimino{
-        static mut PREV: u8 = 7;
-        let mut rand_num = rand::random::<u8>() % 7;
-        if unsafe{ PREV } == rand_num {
-            rand_num = rand::random::<u8>() % 7;
-        }
-        unsafe {PREV = rand_num};
-        match rand_num {
+    fn create_at_random(randomizer: &mut Randomizer) -> Tetrimino{
+        Tetrimino::from_kind(randomizer.next_kind())
+    }
+    //➔ The kind of a tetrimino is its color minus one (0 => I, 1 => O, ... 6 => L)
+    fn from_kind(kind: u8) -> Tetrimino {
+        match kind {
             0 => TetriminoI::new(),
             1 => TetriminoO::new(),
             2 => TetriminoT::new(),
@@ -222,6 +259,9 @@ impl Tetrimino {
             _ => unreachable!(),
         }
     }
+    fn kind(&self) -> u8 {
+        self.states[0].iter().flatten().find(|&&block| block != 0).map_or(0, |block| block - 1)
+    }
     fn test_position(&self, game_map: &[Vec<u8>], tmp_state: usize, x: isize, y: usize) -> bool{
         
         //test for whether a current state is viable to use.
@@ -242,24 +282,64 @@ impl Tetrimino {
         }
         return true;
     }
-    fn rotate(&mut self, game_map: &[Vec<u8>]) {
-        let mut tmp_state = self.current_state + 1;
-        if tmp_state >= self.states.len() {
-            tmp_state = 0;
-        }
+    //➔ The state the piece is in after `step` clockwise quarter turns
+    fn rotated_state(&self, step: isize) -> usize {
+        (self.current_state as isize + step).rem_euclid(self.states.len() as isize) as usize
+    }
+    //➔ `step` is the number of clockwise quarter turns, negative for counter-clockwise.
+    //   `kicks` are the sideways moves tried for the piece to fit, in order
+    fn rotate_by(&mut self, game_map: &[Vec<u8>], step: isize, kicks: &[isize]) {
+        let tmp_state = self.rotated_state(step);
         //➔ Adjustment for adjusting x position of the tetrimino not fall out of grid boundary
-        let adjustments = [0, -1, 1, -2, 2, -3, 3];
-        'adjustment_for: for adjustment in adjustments.into_iter(){
+        'adjustment_for: for &adjustment in kicks.iter(){
             if self.test_position(game_map, tmp_state, self.x + adjustment, self.y) {
                 self.current_state = tmp_state;
+                self.x += adjustment;
                 break 'adjustment_for;
             }
         }
     }
+    //➔ Whether the piece is a T with at least three of the four cells diagonal to its center
+    //   taken, the walls and floor counting as taken
+    fn is_t_spin(&self, game_map: &[Vec<u8>]) -> bool {
+        if self.kind() != 2 {
+            return false;
+        }
+        let state = &self.states[self.current_state];
+        let filled = |row: isize, col: isize| {
+            row >= 0 && col >= 0 && (row as usize) < state.len() && (col as usize) < state[row as usize].len()
+                && state[row as usize][col as usize] != 0
+        };
+        //➔ the center is the block the three others are next to
+        let center = (0..4).flat_map(|row| (0..4).map(move |col| (row, col))).find(|&(row, col)| {
+            filled(row, col) && [(-1, 0), (1, 0), (0, -1), (0, 1)].iter()
+                .filter(|&&(d_row, d_col)| filled(row + d_row, col + d_col)).count() == 3
+        });
+        let (row, col) = match center {
+            Some(center) => center,
+            None => return false,
+        };
+        let taken = [(-1, -1), (-1, 1), (1, -1), (1, 1)].iter().filter(|&&(d_row, d_col)| {
+            let y = self.y as isize + row + d_row;
+            let x = self.x + col + d_col;
+            y < 0 || x < 0 || y as usize >= game_map.len() || x as usize >= game_map[y as usize].len()
+                || game_map[y as usize][x as usize] != 0
+        }).count();
+        taken >= 3
+    }
     fn test_current_position(&self, game_map: &[Vec<u8>]) -> bool {
         self.test_position(game_map, self.current_state as usize, self.x, self.y)
     }
 
+    //➔ The y position the tetrimino would land on if it was dropped straight down
+    fn drop_position(&self, game_map: &[Vec<u8>]) -> usize {
+        let mut y = self.y;
+        while self.test_position(game_map, self.current_state, self.x, y + 1) {
+            y += 1;
+        }
+        y
+    }
+
     fn change_position(&mut self, game_map: &[Vec<u8>], new_x: isize, new_y: usize) -> bool{
         if self.test_position(game_map, self.current_state as usize, new_x, new_y) {
             self.x = new_x;
@@ -273,45 +353,123 @@ impl Tetrimino {
 }
 
 type Grid = Vec<Vec<u8>>;
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Tetris {
     game_map: Grid,
+    //➔ Columns and rows of the game map
+    width: usize,
+    height: usize,
+    //➔ Engine time every block of the game map was locked at, in the shape of the game map
+    lock_times: Vec<Vec<Duration>>,
     current_level: u32,
     score: u32,
     num_lines: u32,
     current_piece: Option<Tetrimino>,
+    randomizer: Randomizer,
+    //➔ Kinds of the pieces coming after the current one, the first one comes next
+    next_kinds: VecDeque<u8>,
+    //➔ How many pieces of each kind have been locked so far
+    piece_counts: [u32; 7],
+    //➔ Kind of the piece put on hold, which can be swapped once per piece
+    held_kind: Option<u8>,
+    hold_used: bool,
+    //➔ Whether hold may be used at all, on top of what the ruleset allows
+    hold_enabled: bool,
+    //➔ Whether a new kind is drawn whenever a piece leaves the queue, puzzles only have the pieces they come with
+    deals: bool,
+    //➔ Seed the randomizer started from, a game with the same seed deals the same pieces
+    seed: u64,
+    //➔ Gravity, level ups, randomizer and what the player may use
+    ruleset: Ruleset,
+    //➔ Level the game started from, which the NES rules need for the first level up
+    start_level: u32,
+    scoring: Scoring,
+    //➔ Pieces in a row that cleared lines, reset by a piece that clears nothing
+    combo: u32,
+    //➔ Whether the last clear was a tetris, for the back-to-back bonus
+    //➔ the last clear was a tetris or a T-spin, for the guideline back to back
+    last_clear_difficult: bool,
+    //➔ How many of the lines cleared held garbage
+    garbage_cleared: u32,
+    //➔ Cells the current piece was dropped by the player, which the master scoring pays for along with a clear
+    dropped_cells: u32,
 }
 
 impl Tetris {
     fn new() -> Tetris{
+        Tetris::from_seed(rand::random::<u64>())
+    }
+    fn from_seed(seed: u64) -> Tetris {
+        Tetris::with_ruleset(seed, Ruleset::Standard)
+    }
+    fn with_ruleset(seed: u64, ruleset: Ruleset) -> Tetris {
+        let mut randomizer = Randomizer::from_seed(seed);
         let mut game_map = Vec::new();
-        for _ in 0..16 {
-            game_map.push(vec![0u8;10]);
+        for _ in 0..BOARD_HEIGHT {
+            game_map.push(vec![0u8;BOARD_WIDTH]);
         }
+        let next_kinds = (0..NEXT_PIECES).map(|_| ruleset.next_kind(&mut randomizer)).collect();
         Tetris{
             game_map,
+            width: BOARD_WIDTH,
+            height: BOARD_HEIGHT,
+            lock_times: vec![vec![Duration::ZERO; BOARD_WIDTH]; BOARD_HEIGHT],
             current_level: 1,
             score: 0,
             num_lines: 0,
             current_piece: None,
+            randomizer,
+            next_kinds,
+            piece_counts: [0; 7],
+            held_kind: None,
+            hold_used: false,
+            hold_enabled: true,
+            deals: true,
+            seed,
+            ruleset,
+            start_level: 1,
+            scoring: Scoring::Classic,
+            combo: 0,
+            last_clear_difficult: false,
+            garbage_cleared: 0,
+            dropped_cells: 0,
         }
     }
+    //➔ Replaces the board with an empty one of `width` columns and `height` rows
+    fn resize(&mut self, width: usize, height: usize) {
+        self.width = width;
+        self.height = height;
+        self.game_map = vec![vec![0; width]; height];
+        self.lock_times = vec![vec![Duration::ZERO; width]; height];
+    }
+    fn has_hold(&self) -> bool {
+        self.hold_enabled && self.ruleset.has_hold()
+    }
+    fn pieces_placed(&self) -> u32 {
+        self.piece_counts.iter().sum()
+    }
     fn update_score(&mut self, to_add: u32) {
         self.score += to_add;
     }
+    fn add_drop_points(&mut self, cells: usize, hard: bool) {
+        self.dropped_cells += cells as u32;
+        self.update_score(scoring::drop_points(self.scoring, cells, hard));
+    }
     fn inc_level(&mut self) {
         self.current_level += 1;
     }
     
     fn inc_line(&mut self) {
         self.num_lines += 1;
-        if self.num_lines > LEVEL_LINES[self.current_level as usize - 1] {
+        if self.ruleset.levels_up(self.start_level, self.num_lines) {
             self.inc_level();
         }
     }
-    fn check_lines(&mut self) {
+    //➔ Clears the complete rows the last piece left and scores them, `t_spin` if the piece was a T-spin
+    fn check_lines(&mut self, t_spin: bool) {
         let mut current_row = 0;
         let mut score_to_add = 0u32;
+        let mut cleared = 0;
         while current_row < self.game_map.len() {
             let mut complete = true;
             for x in self.game_map[current_row].iter() {
@@ -321,8 +479,13 @@ impl Tetris {
                 }
             }
             if complete {
+                cleared += 1;
+                if self.game_map[current_row].contains(&GARBAGE) {
+                    self.garbage_cleared += 1;
+                }
                 score_to_add += self.current_level;
                 self.game_map.remove(current_row);
+                self.lock_times.remove(current_row);
                 current_row -= 1;
             }
             current_row += 1;
@@ -330,17 +493,142 @@ impl Tetris {
         if self.game_map.len() == 0 {
             score_to_add += 1000;
         }
-        while self.game_map.len() < 16 {
+        //➔ The other scorings use the level before the lines are counted, as the classic one does
+        match self.scoring {
+            Scoring::Classic => {},
+            Scoring::Guideline => {
+                let difficult = cleared == 4 || (t_spin && cleared > 0);
+                if cleared > 0 {
+                    let back_to_back = difficult && self.last_clear_difficult;
+                    score_to_add = scoring::line_clear_points(cleared, self.current_level, t_spin, back_to_back, self.combo);
+                    self.combo += 1;
+                    self.last_clear_difficult = difficult;
+                } else {
+                    //➔ a T-spin clearing nothing still scores, and keeps a back to back going
+                    score_to_add = if t_spin { scoring::line_clear_points(0, self.current_level, true, false, 0) } else { 0 };
+                    self.combo = 0;
+                }
+            },
+            Scoring::Nes => score_to_add = scoring::nes_line_clear_points(cleared, self.current_level),
+            //➔ the master combo grows by one for every line past the first of each clear in a row
+            Scoring::Master => {
+                score_to_add = 0;
+                if cleared > 0 {
+                    self.combo += 2 * cleared as u32 - 2;
+                    let bravo = self.game_map.iter().all(|row| row.iter().all(|&block| block == 0));
+                    score_to_add = scoring::master_line_clear_points(cleared, self.current_level,
+                        self.dropped_cells, self.combo + 1, bravo);
+                } else {
+                    self.combo = 0;
+                }
+            },
+        }
+        self.dropped_cells = 0;
+        while self.game_map.len() < self.height {
             self.inc_line();
-            self.game_map.insert(0, vec![0u8; 10]);
+            self.game_map.insert(0, vec![0u8; self.width]);
+            self.lock_times.insert(0, vec![Duration::ZERO; self.width]);
         }
         self.update_score(score_to_add);
         
     }
-    fn create_new_tetrimino(&self) -> Tetrimino{
-        Tetrimino::create_at_random()
+    //➔ Empties the whole board, the pieces and score are kept
+    fn clear_board(&mut self) {
+        for row in self.game_map.iter_mut() {
+            row.fill(0);
+        }
     }
-    fn make_permanent(&mut self) {
+    //➔ Flips the board left to right, the pieces and score are kept
+    fn mirror(&mut self) {
+        for row in self.game_map.iter_mut() {
+            row.reverse();
+        }
+        for row in self.lock_times.iter_mut() {
+            row.reverse();
+        }
+    }
+    //➔ Rows of the board holding garbage
+    fn garbage_rows(&self) -> u32 {
+        self.game_map.iter().filter(|row| row.contains(&GARBAGE)).count() as u32
+    }
+    //➔ Pushes a garbage row in from the bottom, moving the rest of the board up.
+    //   Returns false if blocks were pushed out at the top
+    fn add_garbage(&mut self, row: Vec<u8>) -> bool {
+        let top = self.game_map.remove(0);
+        self.lock_times.remove(0);
+        self.lock_times.push(vec![Duration::ZERO; row.len()]);
+        self.game_map.push(row);
+        top.iter().all(|&block| block == 0)
+    }
+    //➔ Moves the current piece up a row if the board moved into it,
+    //   returns false if there is no room for it there either
+    fn push_piece_up(&mut self) -> bool {
+        let piece = match self.current_piece {
+            Some(ref mut piece) => piece,
+            None => return true,
+        };
+        if piece.test_current_position(&self.game_map) {
+            return true;
+        }
+        piece.y > 0 && piece.change_position(&self.game_map, piece.x, piece.y - 1)
+    }
+    fn create_new_tetrimino(&mut self) -> Tetrimino{
+        //➔ The next piece comes out of the queue and a new one is drawn to keep it full
+        if self.deals {
+            self.next_kinds.push_back(self.ruleset.next_kind(&mut self.randomizer));
+        }
+        let piece = match self.next_kinds.pop_front() {
+            Some(kind) => self.ruleset.new_piece(kind),
+            None => Tetrimino::create_at_random(&mut self.randomizer),
+        };
+        self.place_at_spawn(piece)
+    }
+    //➔ Moves a new piece to where it comes in on this board: as far across a narrower
+    //   board as on the normal one, without sticking out on the right
+    fn place_at_spawn(&self, mut piece: Tetrimino) -> Tetrimino {
+        if self.width != BOARD_WIDTH {
+            let columns = piece.states[0].iter()
+                .filter_map(|row| row.iter().rposition(|&block| block != 0))
+                .max().map_or(0, |last| last + 1);
+            piece.x = (piece.x * self.width as isize / BOARD_WIDTH as isize).min((self.width - columns) as isize);
+        }
+        piece
+    }
+    //➔ Swaps the current piece with the held one, or with the next piece when nothing is held yet.
+    //   Returns whether the swap happened
+    fn hold(&mut self) -> bool {
+        if self.hold_used || !self.has_hold() {
+            return false;
+        }
+        let current_kind = match self.current_piece {
+            Some(ref piece) => piece.kind(),
+            None => return false,
+        };
+        let piece = match self.held_kind {
+            Some(kind) => self.place_at_spawn(self.ruleset.new_piece(kind)),
+            None => {
+                //➔ only taken from the queue once the held piece is known to fit
+                let kind = match self.next_kinds.front() {
+                    Some(&kind) => kind,
+                    None => return false,
+                };
+                self.place_at_spawn(self.ruleset.new_piece(kind))
+            },
+        };
+        if !piece.test_current_position(&self.game_map) {
+            return false;
+        }
+        if self.held_kind.is_none() {
+            self.current_piece = Some(self.create_new_tetrimino());
+        } else {
+            self.current_piece = Some(piece);
+        }
+        self.held_kind = Some(current_kind);
+        self.hold_used = true;
+        true
+    }
+    //➔ Locks the current piece into the game map at engine time `now`, `t_spin` if it turned into place
+    fn make_permanent(&mut self, now: Duration, t_spin: bool) {
         let mut to_add = 0;
 
         let mut shift_y = 0;
@@ -362,75 +650,25 @@ impl Tetris {
                         let y = shift_y + piece.y;
                         //➔ Update the game map to hold the block
                         self.game_map[y as usize][x as usize] = piece.states[piece.current_state as usize][shift_y][shift_x];
+                        self.lock_times[y as usize][x as usize] = now;
                     }
                     shift_x += 1;
                 }
                 shift_y += 1;
             }
-            to_add += self.current_level;
-        }
-        self.update_score(to_add);
-        self.check_lines();
-    }
-}
-
-fn handle_events(tetris: &mut Tetris, quit: &mut bool, timer: &mut SystemTime, event_pump: &mut sdl2::EventPump) -> bool {
-    let mut make_permanent = false;
-
-    if let Some(ref mut piece) = tetris.current_piece {
-
-        let mut tmp_x = piece.x;
-        let mut tmp_y = piece.y;
-        for event in event_pump.poll_iter() {
-            match event  {
-                Event::Quit {..} |
-                Event::KeyDown {keycode: Some(Keycode::Escape), ..} => {
-                    *quit = true;
-                    break
-                },
-                Event::KeyDown {keycode: Some(Keycode::Left), ..} => {
-                    tmp_x -= 1;
-                },
-                Event::KeyDown {keycode: Some(Keycode::Right), ..} => {
-                    tmp_x += 1;
-                },
-                Event::KeyDown {keycode: Some(Keycode::Up), ..} => {
-                    piece.rotate(&tetris.game_map);
-                },
-                Event::KeyDown {keycode: Some(Keycode::Down), ..} => {
-                    tmp_y += 1;
-                },
-                Event::KeyDown {keycode: Some(Keycode::Space), ..} => {
-                    let x = piece.x;
-                    let mut y = piece.y;
-                    
-                    while piece.change_position(&tetris.game_map, x, y + 1) == true {
-                        y += 1;
-                    }
-                    make_permanent = true;
-                },
-                _ => {}
-            }
-        } // for 
-        if !make_permanent {
-            //If piece can not change position to (tmp_x, tmp_y) and current y position
-            //is not the previous y position
-            if piece.change_position(&tetris.game_map, tmp_x, tmp_y) == false 
-                && tmp_y != piece.y
-            {
-                make_permanent = true;
+            if self.scoring == Scoring::Classic {
+                to_add += self.current_level;
             }
+            self.piece_counts[piece.kind() as usize] += 1;
+            self.hold_used = false;
         }
-    } // if let
-    if make_permanent {
-        tetris.make_permanent();
-        *timer = SystemTime::now();
+        self.update_score(to_add);
+        self.check_lines(t_spin);
     }
-    make_permanent
 }
 
 fn write_into_file(content: &str, filename: &str) -> io::Result<()> {
-    let mut f = File::open(filename)?;
+    let mut f = File::create(filename)?;
     f.write_all(content.as_bytes())
 }
 
@@ -454,7 +692,7 @@ fn line_to_slice(line: &str) -> Vec<u32> {
     //     item.parse::<u32>()
     // }).collect::<Vec<u32>>()
 
-    line.split(" ").filter_map( |item| {
+    line.split_whitespace().filter_map( |item| {
         item.parse::<u32>().ok()
     }).collect::<Vec<u32>>()
 }
@@ -478,210 +716,182 @@ fn load_highscores_and_lines() -> Option<(Vec<u32>, Vec<u32>)> {
     }
     None
 }
+//➔ Keeps the best NUM_HIGHSCORES values, highest first. Returns whether `value` made it in
 fn update_vec(v: &mut Vec<u32>, value: u32) -> bool{
-    if v.len() < NUM_HIGHSCORES {
-        v.push(value);
-        v.sort();
-        return true;
-    }else {
-        for entry in v.iter_mut(){ 
-            if value > *entry { 
-                *entry = value;
-                return true;
-            }
-        }
+    if v.len() >= NUM_HIGHSCORES && v.iter().all(|entry| value <= *entry) {
+        return false;
     }
-    return false;
+    v.push(value);
+    v.sort_by(|a, b| b.cmp(a));
+    v.truncate(NUM_HIGHSCORES);
+    true
 }
 fn print_game_information(tetris: &Tetris) {
     let mut highest_score = true;
-    let mut highest_lines = true;
 
-    if let Some((mut highscores, mut num_lines)) = load_highscores_and_lines() {
-        if !update_vec(&mut highscores, tetris.score){ 
-            highest_score = false;
-        }
-        if !update_vec(&mut num_lines, tetris.num_lines) {
-            highest_lines = false;
-        }
-        if highest_score || highest_lines  {
-            save_highscores_and_lines(&highscores, &num_lines);
-        }
-        println!("-------------------GAME OVER-----------------------");
-        println!("Score: {}{}", tetris.score, if highest_score {"[New Record]"}else{""});
-        println!("Num lines: {}{}", tetris.num_lines, if highest_score{"[New Record]"}else{""});
-        println!("Current Level: {}", tetris.current_level);
+    //➔ Without a highscore file yet, this game starts a fresh one
+    let (mut highscores, num_lines_sent) = load_highscores_and_lines().unwrap_or_default();
+    if !update_vec(&mut highscores, tetris.score){ 
+        highest_score = false;
     }
-}
-
-fn is_time_over(tetris: &Tetris, timer: &SystemTime)->bool{
-    let millis = match timer.elapsed() {
-        Ok(elapsed) => elapsed.as_secs() as u32 * 1000 + elapsed.subsec_nanos() / 1_000_000,
-        Err(_) => return false
-    };
-    if millis > LEVEL_TIMES[tetris.current_level as usize]{
-        true
-    }else{
-        false
+    if highest_score {
+        save_highscores_and_lines(&highscores, &num_lines_sent);
     }
+    println!("-------------------GAME OVER-----------------------");
+    println!("Score: {}{}", tetris.score, if highest_score {"[New Record]"}else{""});
+    println!("Num lines: {}", tetris.num_lines);
+    println!("Current Level: {}", tetris.current_level);
 }
 
-fn create_texture_rect<'a>(canvas: &mut Canvas<Window>, 
-    texture_creator: &'a TextureCreator<WindowContext>,
-     r: u8, g: u8, b: u8, 
-     width: u32, height: u32) -> Option<Texture<'a>> {
-    
-        if let Ok(mut texture) = texture_creator.create_texture_target(None, width, height) {
-            canvas.with_texture_canvas(&mut texture, |texture| {
-                texture.set_draw_color(Color::RGB(r, g, b));
-                texture.clear();
-            }).expect("Failed to color a texture!");
-            Some(texture)
-        }else {
-            None
-        }
+//➔ Keeps the garbage lines a player sent in a versus game along with the highscores.
+//   Returns whether it is one of the best NUM_HIGHSCORES
+fn save_lines_sent(lines_sent: u32) -> bool {
+    let (highscores, mut num_lines_sent) = load_highscores_and_lines().unwrap_or_default();
+    if !update_vec(&mut num_lines_sent, lines_sent) {
+        return false;
+    }
+    if !save_highscores_and_lines(&highscores, &num_lines_sent) {
+        println!("Could not save the lines sent to {}", HIGHSCORE_FILE);
+    }
+    true
 }
 
 fn main() -> Result<(), Box<dyn Error>>{
     
+    let (mut settings, errors) = Settings::load();
+    for error in errors.iter() {
+        println!("{}", error);
+    }
+
     let sdl_context = sdl2::init()?;
-    let mut tetris = Tetris::new();
-    let mut timer = SystemTime::now();
     let mut event_pump = sdl_context.event_pump()?;
-    let width = 1360u32;
-    let height = 768u32;
-    let grid_x = (width - TETRIS_HEIGHT as u32 * 10) as i32 / 2;
-    let grid_y = (height - TETRIS_HEIGHT as u32 * 16) as i32 / 2;
 
     let video_subsystem = sdl_context.video()?;
 
-    let window = video_subsystem.window("Tetris", width, height)
-        .position_centered()
-        .build()?;
+    let mut window = video_subsystem.window("Tetris", settings.window_width, settings.window_height);
+    window.position_centered();
+    if settings.fullscreen {
+        window.fullscreen_desktop();
+    }
+    let window = window.build()?;
     
-    let mut canvas = window.into_canvas()
+    let canvas = window.into_canvas()
         .target_texture()
         .present_vsync()    //to enable v-sync
         .build()?;
     
     let texture_creator: TextureCreator<_> = canvas.texture_creator();
-
-    let grid = create_texture_rect(&mut canvas, 
-        &texture_creator, 255, 255, 255, 
-        width - 2 * grid_x as u32,
-        height - 2 * grid_y as u32).expect("failed to create the grid texture");
-    
-    let border = create_texture_rect(&mut canvas,
-         &texture_creator, 0, 0, 0, 
-         width - 2 * grid_x as u32 + 10,
-         height - 2 * grid_y as u32 + 10).expect("failed to create the border texture");
-
-    macro_rules! texture {
-        ($r: expr, $g: expr, $b: expr) => (
-            create_texture_rect(&mut canvas,
-                &texture_creator, 
-                $r, $g, $b, 
-                TETRIS_HEIGHT as u32, 
-                TETRIS_HEIGHT as u32).unwrap()
-        )
-    }
-
-    //fixed-length array
-    let textures = [texture!(255, 69, 69), texture!(255, 220, 69), texture!(237, 150, 37), texture!(171, 99, 237),
-                        texture!(77, 149, 239), texture!(39, 218, 225), texture!(45, 216, 47)];
-    
-    
-    
+    let mut graphics = Graphics::new(canvas, &texture_creator, settings.theme);
+    let mut gamepads = Gamepads::new(sdl_context.game_controller()?);
+
+    let mut setup = GameSetup::default();
+
+    //➔ `--continue` skips the title screen and resumes the saved game right away,
+    //   `--host [port]` and `--connect address` go straight into a network versus game
+    let args: Vec<String> = std::env::args().collect();
+    let mut action = if args.iter().any(|arg| arg == "--continue") {
+        TitleAction::Continue
+    } else if let Some(role) = netplay::role_from_args(&args) {
+        TitleAction::Network(role)
+    } else {
+        screens::title_screen(&mut graphics, &mut event_pump, &mut gamepads, &mut settings, &mut setup)
+    };
     loop {
-        if is_time_over(&tetris, &timer) {
-            #[allow(unused_assignments)]
-            let mut make_permanent = false;
-            if let Some(ref mut piece) = tetris.current_piece {
-                let x = piece.x;
-                let y = piece.y + 1;
-                make_permanent = !piece.change_position(&tetris.game_map, x, y);
-
-                if make_permanent {
-                    tetris.make_permanent();
-                }
-                timer = SystemTime::now();
-            }
-            //➔ set the background of the canvas
-            canvas.set_draw_color(Color::RGB(255, 0, 0));
-            canvas.clear();
-
-            //➔ draw the border
-            canvas.copy(&border, None, Rect::new(
-                grid_x - 10,
-                grid_y - 10,
-                TETRIS_HEIGHT as u32 * 10 + 20, 
-                TETRIS_HEIGHT as u32 * 16 + 20)).expect("failed to render the border");
-
-            //➔ draw the grid
-            canvas.copy(&grid, None, Rect::new(
-                grid_x, 
-                grid_y, 
-                TETRIS_HEIGHT as u32 * 10,
-                TETRIS_HEIGHT as u32 * 16)).expect("failed to render the grid");
-
-            //if no tetrimino is available
-            if tetris.current_piece.is_none() {
-                let current_piece = tetris.create_new_tetrimino();
-                if !current_piece.test_current_position(&tetris.game_map) {
-                    print_game_information(&tetris);
-                    return Ok(());
-                }
-                tetris.current_piece = Some(current_piece);
-            }
+        let end = match action {
+            TitleAction::Continue => match save::load_game(&mut setup) {
+                //➔ the save stays until the game ends or the next autosave replaces it, so a crash loses nothing
+                Ok(saved) => {
+                    game::run_game(&mut graphics, &mut event_pump, &mut gamepads, &mut settings, &setup, Some(saved))?
+                },
+                Err(e) => {
+                    println!("Could not continue the saved game: {}", e);
+                    GameEnd::Menu
+                },
+            },
+            TitleAction::Play => game::run_game(&mut graphics, &mut event_pump, &mut gamepads, &mut settings, &setup, None)?,
+            TitleAction::Versus => versus::run_versus(&mut graphics, &mut event_pump, &mut gamepads, &mut settings, &setup)?,
+            TitleAction::Network(ref role) => {
+                netplay::run_network(&mut graphics, &mut event_pump, &mut gamepads, &settings, &setup, role)?
+            },
+            TitleAction::Daily => {
+                let daily = daily::daily_setup(daily::today());
+                game::run_game(&mut graphics, &mut event_pump, &mut gamepads, &mut settings, &daily, None)?
+            },
+            TitleAction::WatchReplay => match Replay::load() {
+                Ok(replay) => game::watch_replay(&mut graphics, &mut event_pump, &settings, replay)?,
+                Err(e) => {
+                    println!("Could not load the replay: {}", e);
+                    GameEnd::Menu
+                },
+            },
+            TitleAction::Exit => return Ok(()),
+        };
+        action = match end {
+            GameEnd::Exit => return Ok(()),
+            //➔ Straight into the next game, the window and textures stay as they are
+            //➔ a daily challenge is retried as another attempt at it, with its own seed
+            GameEnd::Retry(_) if matches!(action, TitleAction::Daily) => TitleAction::Daily,
+            GameEnd::Retry(seed) if matches!(action, TitleAction::Versus) => {
+                setup.seed = seed;
+                TitleAction::Versus
+            },
+            GameEnd::Retry(seed) => {
+                setup.seed = seed;
+                TitleAction::Play
+            },
+            GameEnd::NextPuzzle => {
+                setup.puzzle_index += 1;
+                TitleAction::Play
+            },
+            GameEnd::Menu => {
+                setup.seed = None;
+                screens::title_screen(&mut graphics, &mut event_pump, &mut gamepads, &mut settings, &mut setup)
+            },
+        };
+    }
+}
 
-            let mut quit = false;
-            if !handle_events(&mut tetris, &mut quit, &mut timer, &mut event_pump) {
-                if let Some(ref mut piece) = tetris.current_piece {
-                    
-                    //➔ Draw the current tetrimino 
-                    for (row_num, _row_val) in piece.states[piece.current_state].iter().enumerate() {
-                        for (col_num, &col_val) in piece.states[piece.current_state][row_num].iter().enumerate() {
-                            if col_val != 0 {
-                                canvas.copy(&textures[col_val as usize - 1], None, 
-                                    Rect::new(
-                                        grid_x + (piece.x + col_num as isize) as i32 * TETRIS_HEIGHT as i32, 
-                                        grid_y + (piece.y + row_num as usize) as i32 * TETRIS_HEIGHT as i32,    
-                                        TETRIS_HEIGHT as u32,
-                                        TETRIS_HEIGHT as u32
-                                    )
-                                ).expect("failed to render tetrimino");
-                            }
-                        }
-                    }
-                }
-            }
-            if quit {
-                print_game_information(&tetris);
-                return Ok(());
-            }
-            //We need to draw the game map in here.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mirroring_twice_gives_back_the_board() {
+        let mut tetris = Tetris::from_seed(1);
+        let bottom = BOARD_HEIGHT - 1;
+        tetris.game_map[bottom] = vec![1, 1, 0, 2, 0, 0, 3, 0, 0, 4];
+        tetris.game_map[bottom - 1][0] = 5;
+        tetris.lock_times[bottom][0] = Duration::from_secs(3);
+        let (map, lock_times) = (tetris.game_map.clone(), tetris.lock_times.clone());
+
+        tetris.mirror();
+        assert_eq!(tetris.game_map[bottom], vec![4, 0, 0, 3, 0, 0, 2, 0, 1, 1]);
+        assert_eq!(tetris.game_map[bottom - 1][BOARD_WIDTH - 1], 5);
+        assert_eq!(tetris.lock_times[bottom][BOARD_WIDTH - 1], Duration::from_secs(3));
+
+        tetris.mirror();
+        assert_eq!(tetris.game_map, map);
+        assert_eq!(tetris.lock_times, lock_times);
+    }
 
-            //➔ Draw the game map
-            for (row_num, _row_val) in tetris.game_map.iter().enumerate() {
-                for (col_num, col_val) in tetris.game_map[row_num].iter().enumerate() {
-                    if *col_val == 0 {
-                        continue;
+    #[test]
+    fn big_pieces_come_in_inside_the_narrow_board() {
+        for ruleset in [Ruleset::Standard, Ruleset::Nes, Ruleset::Ars] {
+            let mut tetris = Tetris::with_ruleset(1, ruleset);
+            tetris.resize(BOARD_WIDTH / 2, BOARD_HEIGHT / 2);
+            for kind in 0..7 {
+                let piece = tetris.place_at_spawn(ruleset.new_piece(kind));
+                for (y, row) in piece.states[piece.current_state].iter().enumerate() {
+                    for (x, &block) in row.iter().enumerate() {
+                        if block != 0 {
+                            let column = piece.x + x as isize;
+                            assert!((0..tetris.width as isize).contains(&column),
+                                "{:?} piece {} has a block in column {} (row {})", ruleset, kind, column, y);
+                        }
                     }
-                    canvas.copy(&textures[*col_val as usize - 1], None,
-                        Rect::new(
-                            grid_x + (col_num as i32 * TETRIS_HEIGHT as i32),
-                            grid_y + (row_num as i32 * TETRIS_HEIGHT as i32),
-                            TETRIS_HEIGHT as u32, 
-                            TETRIS_HEIGHT as u32
-                        )
-                    ).expect("failed to render game map into window");
                 }
+                assert!(piece.test_current_position(&tetris.game_map), "{:?} piece {} does not fit", ruleset, kind);
             }
-            //➔ present the window
-            canvas.present();
-
-            //sleep enough to get the 60fps frame rate
-            thread::sleep(Duration::new(0, 1_000_000_000u32 / 60));
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,312 @@
+//➔ Master mode, after the Grand Master games.
+//
+// The level goes from 0 to 999: every piece that comes in adds one and every
+// cleared line adds one more, but pieces alone cannot take the level past the
+// last level of a section (99, 199, ... 998), only a clear can. The gravity of
+// the ARS rules goes up with the level until pieces drop to the floor at once,
+// and the delays shrink in the later sections: the entry delay (ARE) before
+// each piece, the delay after a clear, and the lock delay a piece gets on the
+// ground before it locks. Rotating or holding during the entry delay turns or
+// holds the next piece as it comes in (IRS and IHS).
+//
+// The grade follows the score, and the grand master grade is only given for
+// reaching level 999 fast enough with enough points on the way.
+
+use crate::ruleset::{ars_gravity, TWENTY_G};
+use crate::settings::Action;
+use crate::Tetris;
+
+use std::time::Duration;
+
+//➔ The game is over once the level gets there
+pub const MAX_LEVEL: u32 = 999;
+pub const SECTION_LEVELS: u32 = 100;
+
+//➔ Delays from the level they start at, in frames at 60 frames per second:
+//   entry delay, entry delay after a clear, lock delay and line clear delay
+const TIMINGS: [(u32, u32, u32, u32, u32); 6] = [
+    (0, 25, 25, 30, 40),
+    (500, 25, 25, 30, 25),
+    (600, 25, 16, 30, 16),
+    (700, 16, 12, 30, 12),
+    (800, 12, 6, 30, 6),
+    (900, 12, 6, 17, 6),
+];
+
+//➔ Grades and the score they take, from the lowest one
+const GRADES: [(&str, u32); 18] = [("9", 0), ("8", 400), ("7", 800), ("6", 1400), ("5", 2000), ("4", 3500),
+    ("3", 5500), ("2", 8000), ("1", 12000), ("S1", 16000), ("S2", 22000), ("S3", 30000), ("S4", 40000),
+    ("S5", 52000), ("S6", 66000), ("S7", 82000), ("S8", 100000), ("S9", 120000)];
+pub const GRAND_MASTER: &str = "GM";
+
+//➔ Levels checked on the way to the grand master grade, with the time they must be reached
+//   by and the score they must be reached with
+const GM_CHECKS: [(u32, Duration, u32); 3] = [
+    (300, Duration::from_secs(4 * 60 + 15), 12000),
+    (500, Duration::from_secs(7 * 60 + 30), 40000),
+    (MAX_LEVEL, Duration::from_secs(13 * 60 + 30), 126000),
+];
+
+//➔ Gravity owed to a piece is counted in 1/256000 rows: 1/256 rows per frame, times 60 frames per 1000 ms
+const ROW: u32 = 256 * 1000;
+//➔ Soft dropping falls at least a row per frame
+const SOFT_DROP_GRAVITY: u32 = 256;
+
+fn frames(frames: u32) -> Duration {
+    Duration::from_millis(frames as u64 * 1000 / 60)
+}
+
+fn timings(level: u32) -> (u32, u32, u32, u32, u32) {
+    TIMINGS.iter().rev().copied().find(|&(from, ..)| level >= from).unwrap_or(TIMINGS[0])
+}
+
+//➔ Index of the grade `score` reaches in GRADES
+pub fn grade_index(score: u32) -> usize {
+    GRADES.iter().rposition(|&(_, needed)| score >= needed).unwrap_or(0)
+}
+
+pub fn grade_name(index: usize) -> &'static str {
+    GRADES.get(index).map_or(GRAND_MASTER, |&(name, _)| name)
+}
+
+//➔ Score the grade after `index` takes, none past the highest one
+pub fn next_grade_score(index: usize) -> Option<u32> {
+    GRADES.get(index + 1).map(|&(_, needed)| needed)
+}
+
+//➔ The last level pieces alone can take the game to, in the section of `level`
+fn section_stop(level: u32) -> u32 {
+    (level / SECTION_LEVELS * SECTION_LEVELS + SECTION_LEVELS - 1).min(MAX_LEVEL - 1)
+}
+
+pub struct Master {
+    //➔ engine time the next piece comes in at
+    pub next_spawn: Duration,
+    //➔ quarter turns and hold asked for during the entry delay, for the next piece
+    initial_rotation: isize,
+    initial_hold: bool,
+    //➔ gravity owed to the current piece, in 1/ROW rows
+    gravity: u32,
+    //➔ engine time the piece got onto the ground, or last moved down a row while on it
+    grounded_since: Option<Duration>,
+    //➔ engine time every section was finished at, the first section first
+    pub section_times: Vec<Duration>,
+    //➔ set as long as every check on the way to the grand master grade passed
+    gm_possible: bool,
+}
+
+impl Master {
+    pub fn new() -> Master {
+        Master {
+            next_spawn: Duration::ZERO,
+            initial_rotation: 0,
+            initial_hold: false,
+            gravity: 0,
+            grounded_since: None,
+            section_times: Vec::new(),
+            gm_possible: true,
+        }
+    }
+
+    //➔ Keeps the rotations and hold pressed while no piece is in play, for the next one
+    pub fn buffer(&mut self, action: Action) {
+        match action {
+            Action::Rotate => self.initial_rotation += 1,
+            Action::RotateCcw => self.initial_rotation -= 1,
+            Action::Rotate180 => self.initial_rotation += 2,
+            Action::Hold => self.initial_hold = true,
+            _ => {},
+        }
+    }
+
+    //➔ To be called once the new piece is in play: counts it towards the level and applies
+    //   what was buffered during the entry delay
+    pub fn enter(&mut self, tetris: &mut Tetris, now: Duration) {
+        if tetris.pieces_placed() > 0 && tetris.current_level < section_stop(tetris.current_level) {
+            self.raise_level(tetris, 1, now);
+        }
+        if self.initial_hold {
+            tetris.hold();
+        }
+        if let Some(ref mut piece) = tetris.current_piece {
+            if self.initial_rotation != 0 {
+                let kicks = tetris.ruleset.kicks(piece, &tetris.game_map, self.initial_rotation);
+                piece.rotate_by(&tetris.game_map, self.initial_rotation, kicks);
+            }
+        }
+        self.initial_rotation = 0;
+        self.initial_hold = false;
+        self.reset_piece();
+    }
+
+    //➔ Forgets the gravity and lock delay of the piece, for a piece that starts over from the top
+    pub fn reset_piece(&mut self) {
+        self.gravity = 0;
+        self.grounded_since = None;
+    }
+
+    //➔ To be called once a piece locked, `lines` being how many lines it cleared
+    pub fn locked(&mut self, tetris: &mut Tetris, lines: u32, now: Duration) {
+        let (_, are, line_are, _, clear) = timings(tetris.current_level);
+        self.next_spawn = now + if lines > 0 { frames(line_are + clear) } else { frames(are) };
+        if lines > 0 {
+            self.raise_level(tetris, lines, now);
+        }
+    }
+
+    //➔ Adds `amount` levels, noting the sections finished and the grand master checks passed on the way
+    fn raise_level(&mut self, tetris: &mut Tetris, amount: u32, now: Duration) {
+        let from = tetris.current_level;
+        let to = (from + amount).min(MAX_LEVEL);
+        tetris.current_level = to;
+        if to / SECTION_LEVELS > from / SECTION_LEVELS || to == MAX_LEVEL {
+            self.section_times.push(now);
+        }
+        for &(level, time, score) in GM_CHECKS.iter() {
+            if from < level && to >= level && (now > time || tetris.score < score) {
+                self.gm_possible = false;
+            }
+        }
+    }
+
+    //➔ Rows the piece falls this step at `level`
+    pub fn fall_rows(&mut self, level: u32, soft_drop: bool) -> u32 {
+        let mut gravity = ars_gravity(level);
+        if soft_drop {
+            gravity = gravity.max(SOFT_DROP_GRAVITY);
+        }
+        if gravity >= TWENTY_G {
+            return u32::MAX;
+        }
+        self.gravity += gravity * 60;
+        let rows = self.gravity / ROW;
+        self.gravity %= ROW;
+        rows
+    }
+
+    //➔ Whether a piece on the ground at `level` locks by now. `moved_down` is set when it got
+    //   down a row this step, which gives it its whole lock delay again
+    pub fn lock_due(&mut self, level: u32, on_ground: bool, moved_down: bool, now: Duration) -> bool {
+        if !on_ground {
+            self.grounded_since = None;
+            return false;
+        }
+        if moved_down || self.grounded_since.is_none() {
+            self.grounded_since = Some(now);
+        }
+        let (_, _, _, lock, _) = timings(level);
+        self.grounded_since.is_some_and(|since| now >= since + frames(lock))
+    }
+
+    //➔ Index of the grade the game is at, the grand master grade being past the end of GRADES
+    pub fn grade(&self, tetris: &Tetris) -> usize {
+        if self.gm_possible && tetris.current_level >= MAX_LEVEL {
+            GRADES.len()
+        } else {
+            grade_index(tetris.score)
+        }
+    }
+
+    //➔ Time spent in the current section so far
+    pub fn section_time(&self, now: Duration) -> Duration {
+        now.saturating_sub(self.section_times.last().copied().unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grades_follow_the_score() {
+        let grades = [(0, "9"), (399, "9"), (400, "8"), (5500, "3"), (12000, "1"), (15999, "1"), (16000, "S1"),
+            (100000, "S8"), (120000, "S9"), (500000, "S9")];
+        for (score, grade) in grades {
+            assert_eq!(grade_name(grade_index(score)), grade, "score {}", score);
+        }
+        assert_eq!(next_grade_score(0), Some(400));
+        assert_eq!(next_grade_score(GRADES.len() - 1), None);
+        assert_eq!(grade_name(GRADES.len()), GRAND_MASTER);
+    }
+
+    #[test]
+    fn sections_stop_at_their_last_level() {
+        let stops = [(0, 99), (50, 99), (99, 99), (100, 199), (450, 499), (900, 998), (998, 998)];
+        for (level, stop) in stops {
+            assert_eq!(section_stop(level), stop, "level {}", level);
+        }
+    }
+
+    #[test]
+    fn pieces_stop_at_x99_and_clears_go_past() {
+        let mut tetris = Tetris::from_seed(1);
+        tetris.piece_counts[0] = 1;
+        tetris.current_level = 98;
+        let mut master = Master::new();
+        master.enter(&mut tetris, Duration::ZERO);
+        master.enter(&mut tetris, Duration::ZERO);
+        assert_eq!(tetris.current_level, 99);
+        assert!(master.section_times.is_empty());
+        master.locked(&mut tetris, 2, Duration::from_secs(60));
+        assert_eq!(tetris.current_level, 101);
+        assert_eq!(master.section_times, vec![Duration::from_secs(60)]);
+    }
+
+    #[test]
+    fn delays_shrink_from_the_level_they_start_at() {
+        assert_eq!(timings(0), TIMINGS[0]);
+        assert_eq!(timings(499), TIMINGS[0]);
+        assert_eq!(timings(500), TIMINGS[1]);
+        assert_eq!(timings(899), TIMINGS[4]);
+        assert_eq!(timings(999), TIMINGS[5]);
+        let mut tetris = Tetris::from_seed(1);
+        tetris.current_level = 0;
+        let mut master = Master::new();
+        master.locked(&mut tetris, 0, Duration::ZERO);
+        assert_eq!(master.next_spawn, frames(25));
+        master.locked(&mut tetris, 1, Duration::ZERO);
+        assert_eq!(master.next_spawn, frames(25 + 40));
+    }
+
+    #[test]
+    fn piece_locks_once_its_lock_delay_is_over() {
+        let mut master = Master::new();
+        assert!(!master.lock_due(0, false, false, Duration::ZERO));
+        assert!(!master.lock_due(0, true, false, Duration::ZERO));
+        assert!(!master.lock_due(0, true, false, frames(29)));
+        assert!(master.lock_due(0, true, false, frames(30)));
+        //➔ getting down a row gives the whole delay again
+        let mut master = Master::new();
+        assert!(!master.lock_due(0, true, false, Duration::ZERO));
+        assert!(!master.lock_due(0, true, true, frames(20)));
+        assert!(!master.lock_due(0, true, false, frames(30)));
+        assert!(master.lock_due(0, true, false, frames(50)));
+        //➔ the last section has a shorter lock delay
+        let mut master = Master::new();
+        assert!(!master.lock_due(900, true, false, Duration::ZERO));
+        assert!(master.lock_due(900, true, false, frames(17)));
+    }
+
+    fn reach_the_end(checks: [(u32, Duration); 3], score: u32) -> usize {
+        let mut tetris = Tetris::from_seed(1);
+        tetris.current_level = 0;
+        tetris.score = score;
+        let mut master = Master::new();
+        let mut level = 0;
+        for (to, now) in checks {
+            master.raise_level(&mut tetris, to - level, now);
+            level = to;
+        }
+        master.grade(&tetris)
+    }
+
+    #[test]
+    fn grand_master_needs_every_check_in_time_and_score() {
+        let minutes = |minutes: u64| Duration::from_secs(minutes * 60);
+        let in_time = [(300, minutes(4)), (500, minutes(7)), (MAX_LEVEL, minutes(13))];
+        assert_eq!(grade_name(reach_the_end(in_time, 130000)), GRAND_MASTER);
+        let late = [(300, minutes(5)), (500, minutes(7)), (MAX_LEVEL, minutes(13))];
+        assert_eq!(grade_name(reach_the_end(late, 130000)), "S9");
+        assert_eq!(grade_name(reach_the_end(in_time, 125000)), "S9");
+    }
+}
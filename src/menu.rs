@@ -0,0 +1,94 @@
+//➔ Simple vertical menu navigated with the keyboard.
+
+use crate::font::{draw_text_centered, text_height};
+
+use sdl2::keyboard::Keycode;
+use sdl2::pixels::Color;
+use sdl2::render::Canvas;
+use sdl2::video::Window;
+
+const TITLE_SCALE: u32 = 5;
+const ITEM_SCALE: u32 = 3;
+
+pub enum MenuInput<T> {
+    //➔ Return was pressed on the item
+    Choose(T),
+    //➔ Left (-1) or Right (+1) was pressed on the item, used to change a value in place
+    Adjust(T, i32),
+}
+
+pub struct Menu<T: Copy + PartialEq> {
+    title: String,
+    items: Vec<(String, T)>,
+    selected: usize,
+}
+
+impl<T: Copy + PartialEq> Menu<T> {
+    pub fn new(title: &str, items: Vec<(String, T)>) -> Menu<T> {
+        Menu {
+            title: title.to_string(),
+            items,
+            selected: 0,
+        }
+    }
+
+    pub fn select_prev(&mut self) {
+        if self.selected == 0 {
+            self.selected = self.items.len() - 1;
+        } else {
+            self.selected -= 1;
+        }
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1) % self.items.len();
+    }
+
+    //➔ Moves the selection onto `item`, if the menu has it
+    pub fn select(&mut self, item: T) {
+        if let Some(index) = self.items.iter().position(|(_, value)| *value == item) {
+            self.selected = index;
+        }
+    }
+
+    pub fn selected(&self) -> T {
+        self.items[self.selected].1
+    }
+
+    pub fn set_label(&mut self, item: T, label: String) {
+        if let Some(entry) = self.items.iter_mut().find(|(_, value)| *value == item) {
+            entry.0 = label;
+        }
+    }
+
+    //➔ Moves the selection on Up/Down and reports Return and Left/Right on the selected item
+    pub fn handle_key(&mut self, keycode: Keycode) -> Option<MenuInput<T>> {
+        match keycode {
+            Keycode::Up => self.select_prev(),
+            Keycode::Down => self.select_next(),
+            Keycode::Left => return Some(MenuInput::Adjust(self.selected(), -1)),
+            Keycode::Right => return Some(MenuInput::Adjust(self.selected(), 1)),
+            Keycode::Return | Keycode::KpEnter | Keycode::Space => return Some(MenuInput::Choose(self.selected())),
+            _ => {},
+        }
+        None
+    }
+
+    //➔ Draws the title and the items centered around `center_x`, starting at `y`
+    pub fn draw(&self, canvas: &mut Canvas<Window>, center_x: i32, y: i32) -> Result<(), String> {
+        let white = Color::RGB(255, 255, 255);
+        let highlight = Color::RGB(255, 220, 69);
+
+        draw_text_centered(canvas, &self.title, center_x, y, TITLE_SCALE, white)?;
+        let mut item_y = y + text_height(TITLE_SCALE) as i32 * 2;
+        for (index, (label, _)) in self.items.iter().enumerate() {
+            if index == self.selected {
+                draw_text_centered(canvas, &format!("> {} <", label), center_x, item_y, ITEM_SCALE, highlight)?;
+            } else {
+                draw_text_centered(canvas, label, center_x, item_y, ITEM_SCALE, white)?;
+            }
+            item_y += text_height(ITEM_SCALE) as i32 * 2;
+        }
+        Ok(())
+    }
+}
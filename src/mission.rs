@@ -0,0 +1,297 @@
+//➔ Mission mode: a chain of missions, each to be done within its time limit.
+//
+// The missions are read from the mission file, so new ones can be written
+// without building the game again. Every mission starts with a `mission` line
+// giving its name, followed by `key value...` lines like the other files:
+//
+//   mission TETRIS PAIR
+//   goal tetrises 2
+//   time 60
+//   pieces 12
+//
+// `goal` is one of `lines`, `tetrises`, `tspin_singles`, `tspin_doubles`,
+// `tspin_triples` and `all_clears`, followed by how many of them to do. `time`
+// is the time limit in seconds. The optional `pieces` gives how many pieces the
+// mission can use, `level N` sets the level as the mission starts and
+// `gravity 20g` drops every piece to the floor at once. The board is kept from
+// one mission to the next, and missing a mission ends the run.
+
+use crate::engine::LockResult;
+use crate::save::{invalid, parse_value};
+use crate::{read_from_file, Tetris};
+
+use std::io;
+use std::time::Duration;
+
+pub const MISSION_FILE: &str = "missions.txt";
+
+//➔ What a mission asks to do a number of times
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Clear {
+    Lines,
+    Tetrises,
+    TSpinSingles,
+    TSpinDoubles,
+    TSpinTriples,
+    AllClears,
+}
+
+impl Clear {
+    const ALL: [Clear; 6] = [Clear::Lines, Clear::Tetrises, Clear::TSpinSingles, Clear::TSpinDoubles,
+        Clear::TSpinTriples, Clear::AllClears];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Clear::Lines => "LINES",
+            Clear::Tetrises => "TETRISES",
+            Clear::TSpinSingles => "T-SPIN SINGLES",
+            Clear::TSpinDoubles => "T-SPIN DOUBLES",
+            Clear::TSpinTriples => "T-SPIN TRIPLES",
+            Clear::AllClears => "ALL CLEARS",
+        }
+    }
+
+    fn file_name(&self) -> &'static str {
+        match self {
+            Clear::Lines => "lines",
+            Clear::Tetrises => "tetrises",
+            Clear::TSpinSingles => "tspin_singles",
+            Clear::TSpinDoubles => "tspin_doubles",
+            Clear::TSpinTriples => "tspin_triples",
+            Clear::AllClears => "all_clears",
+        }
+    }
+
+    //➔ How much a piece that locked with `lock` counts towards the goal
+    fn counts(&self, lock: LockResult) -> u32 {
+        match self {
+            Clear::Lines => lock.lines,
+            Clear::Tetrises => (lock.lines == 4) as u32,
+            Clear::TSpinSingles => (lock.t_spin && lock.lines == 1) as u32,
+            Clear::TSpinDoubles => (lock.t_spin && lock.lines == 2) as u32,
+            Clear::TSpinTriples => (lock.t_spin && lock.lines == 3) as u32,
+            Clear::AllClears => lock.perfect_clear as u32,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Mission {
+    pub name: String,
+    pub clear: Clear,
+    pub count: u32,
+    pub time_limit: Duration,
+    //➔ pieces the mission can use, if it is limited
+    pub pieces: Option<u32>,
+    //➔ level the mission is played at, the level reached so far otherwise
+    pub level: Option<u32>,
+    pub twenty_g: bool,
+}
+
+impl Mission {
+    //➔ What the mission asks for: 2 TETRISES IN 12 PIECES
+    pub fn text(&self) -> String {
+        let mut text = format!("{} {}", self.count, self.clear.name());
+        if let Some(pieces) = self.pieces {
+            text.push_str(&format!(" IN {} PIECES", pieces));
+        }
+        if self.twenty_g {
+            text.push_str(" AT 20G");
+        }
+        text
+    }
+}
+
+//➔ The missions of the mission file, in the order of the file
+pub fn load_missions() -> io::Result<Vec<Mission>> {
+    parse_missions(&read_from_file(MISSION_FILE)?)
+}
+
+fn parse_missions(content: &str) -> io::Result<Vec<Mission>> {
+    let mut missions = Vec::new();
+    for line in content.lines() {
+        let mut parts = line.split_whitespace();
+        let key = match parts.next() {
+            Some(key) => key,
+            None => continue,
+        };
+        if key == "mission" {
+            missions.push(Mission {
+                name: parts.collect::<Vec<_>>().join(" "),
+                clear: Clear::Lines,
+                count: 0,
+                time_limit: Duration::ZERO,
+                pieces: None,
+                level: None,
+                twenty_g: false,
+            });
+            continue;
+        }
+        let mission = match missions.last_mut() {
+            Some(mission) => mission,
+            None => return Err(invalid(format!("`{}` before the first mission", key))),
+        };
+        match key {
+            "goal" => {
+                let name: String = parse_value(key, parts.next())?;
+                mission.clear = Clear::ALL.iter().copied().find(|clear| clear.file_name() == name)
+                    .ok_or_else(|| invalid(format!("unknown goal `{}`", name)))?;
+                mission.count = parse_value(key, parts.next())?;
+            },
+            "time" => mission.time_limit = Duration::from_secs(parse_value(key, parts.next())?),
+            "pieces" => mission.pieces = Some(parse_value(key, parts.next())?),
+            "level" => mission.level = Some(parse_value(key, parts.next())?),
+            "gravity" => {
+                let gravity: String = parse_value(key, parts.next())?;
+                mission.twenty_g = match gravity.as_str() {
+                    "20g" => true,
+                    "normal" => false,
+                    _ => return Err(invalid(format!("unknown gravity `{}`", gravity))),
+                };
+            },
+            _ => {},
+        }
+    }
+    //➔ a mission without a goal or a time would be over before it started
+    if let Some(mission) = missions.iter().find(|mission| mission.count == 0 || mission.time_limit.is_zero()) {
+        return Err(invalid(format!("mission `{}` needs a goal and a time", mission.name)));
+    }
+    if missions.is_empty() {
+        return Err(invalid(format!("{} has no missions", MISSION_FILE)));
+    }
+    Ok(missions)
+}
+
+//➔ Where the run stands after a step
+pub enum Status {
+    Playing,
+    //➔ the current mission ran out of time or pieces
+    Failed,
+    //➔ every mission is done
+    Completed,
+}
+
+//➔ A run through the missions
+pub struct Missions {
+    missions: Vec<Mission>,
+    //➔ the mission being played, the number of missions done so far
+    pub index: usize,
+    //➔ engine time the current mission started at
+    started_at: Duration,
+    //➔ pieces placed before the current mission started
+    first_piece: u32,
+    //➔ how much of the goal of the current mission is done
+    pub progress: u32,
+}
+
+impl Missions {
+    pub fn new(missions: Vec<Mission>, tetris: &mut Tetris, now: Duration) -> Missions {
+        let mut run = Missions {
+            missions,
+            index: 0,
+            started_at: now,
+            first_piece: 0,
+            progress: 0,
+        };
+        run.start(tetris, now);
+        run
+    }
+
+    pub fn current(&self) -> Option<&Mission> {
+        self.missions.get(self.index)
+    }
+
+    pub fn count(&self) -> usize {
+        self.missions.len()
+    }
+
+    fn start(&mut self, tetris: &mut Tetris, now: Duration) {
+        self.started_at = now;
+        self.first_piece = tetris.pieces_placed();
+        self.progress = 0;
+        if let Some(level) = self.current().and_then(|mission| mission.level) {
+            tetris.current_level = level;
+        }
+    }
+
+    //➔ To be called once a piece locked
+    pub fn locked(&mut self, lock: LockResult) {
+        if let Some(clear) = self.current().map(|mission| mission.clear) {
+            self.progress += clear.counts(lock);
+        }
+    }
+
+    pub fn twenty_g(&self) -> bool {
+        self.current().is_some_and(|mission| mission.twenty_g)
+    }
+
+    pub fn time_left(&self, now: Duration) -> Option<Duration> {
+        self.current().map(|mission| (self.started_at + mission.time_limit).saturating_sub(now))
+    }
+
+    pub fn pieces_left(&self, tetris: &Tetris) -> Option<u32> {
+        let used = tetris.pieces_placed() - self.first_piece;
+        self.current()?.pieces.map(|pieces| pieces.saturating_sub(used))
+    }
+
+    //➔ Moves on to the next mission once the current one is done, or tells it failed
+    pub fn update(&mut self, tetris: &mut Tetris, now: Duration) -> Status {
+        let mission = match self.current() {
+            Some(mission) => mission,
+            None => return Status::Completed,
+        };
+        if self.progress >= mission.count {
+            self.index += 1;
+            self.start(tetris, now);
+            return if self.current().is_some() { Status::Playing } else { Status::Completed };
+        }
+        if self.time_left(now).is_some_and(|left| left.is_zero()) || self.pieces_left(tetris) == Some(0) {
+            return Status::Failed;
+        }
+        Status::Playing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missions_load_in_the_order_of_the_file() {
+        let content = "mission TETRIS PAIR\n\
+            goal tetrises 2\n\
+            time 60\n\
+            pieces 12\n\
+            \n\
+            mission FAST ONE\n\
+            goal tspin_doubles 1\n\
+            time 30\n\
+            level 10\n\
+            gravity 20g\n";
+        let missions = parse_missions(content).expect("the missions should load");
+        assert_eq!(missions.len(), 2);
+        let first = &missions[0];
+        assert_eq!((first.name.as_str(), first.clear, first.count), ("TETRIS PAIR", Clear::Tetrises, 2));
+        assert_eq!((first.time_limit, first.pieces, first.level, first.twenty_g), (Duration::from_secs(60), Some(12), None, false));
+        assert_eq!(first.text(), "2 TETRISES IN 12 PIECES");
+        let second = &missions[1];
+        assert_eq!((second.clear, second.level, second.twenty_g), (Clear::TSpinDoubles, Some(10), true));
+        assert_eq!(second.text(), "1 T-SPIN DOUBLES AT 20G");
+    }
+
+    #[test]
+    fn bundled_missions_load() {
+        assert!(!parse_missions(include_str!("../missions.txt")).expect("the bundled missions should load").is_empty());
+    }
+
+    #[test]
+    fn bad_missions_are_refused() {
+        assert!(parse_missions("").is_err());
+        assert!(parse_missions("goal lines 4\n").is_err());
+        assert!(parse_missions("mission A\ngoal lines 4\n").is_err());
+        assert!(parse_missions("mission A\ntime 60\n").is_err());
+        assert!(parse_missions("mission A\ngoal singles 4\ntime 60\n").is_err());
+        assert!(parse_missions("mission A\ngoal lines many\ntime 60\n").is_err());
+        assert!(parse_missions("mission A\ngoal lines 4\ntime 60\ngravity fast\n").is_err());
+    }
+}
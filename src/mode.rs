@@ -0,0 +1,626 @@
+//➔ Game modes that can be picked on the title screen, and what each of them adds to a game.
+
+use crate::daily;
+use crate::engine::Engine;
+use crate::game::GameSetup;
+use crate::hud::{format_delta, format_time, format_time_millis, HudEntry};
+use crate::master::{self, SECTION_LEVELS};
+use crate::puzzle;
+use crate::records::{self, Record};
+use crate::ruleset::Ruleset;
+use crate::scoring::Scoring;
+use crate::variant::Variant;
+
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameMode {
+    Marathon,
+    Sprint,
+    Ultra,
+    Dig,
+    Survival,
+    Zen,
+    Master,
+    Invisible,
+    Puzzle,
+    Mission,
+}
+
+//➔ Line goals of a marathon, 0 being endless
+pub const MARATHON_GOALS: [u32; 3] = [150, 200, 0];
+//➔ Line counts a sprint can be played to
+pub const SPRINT_LINES: [u32; 3] = [20, 40, 100];
+//➔ Time limits an ultra can be played with, in seconds
+pub const ULTRA_SECONDS: [u32; 4] = [60, 120, 180, 300];
+//➔ Garbage lines to clear in a dig race
+pub const DIG_LINES: [u32; 3] = [10, 18, 100];
+//➔ Chances in percent for the hole to move between two garbage rows
+pub const DIG_MESSINESS: [u32; 4] = [0, 25, 50, 100];
+//➔ Chance in percent for the hole to move between two rising rows in survival
+pub const SURVIVAL_MESSINESS: u32 = 50;
+//➔ The next rising row is announced this long before it comes
+const RISE_WARNING: Duration = Duration::from_secs(1);
+//➔ Gravity levels zen can be played with, 0 being no gravity
+pub const ZEN_GRAVITY: [u32; 5] = [0, 1, 5, 10, 15];
+//➔ Placements zen can undo
+pub const ZEN_UNDO: [u32; 4] = [0, 5, 20, 100];
+//➔ Seconds locked blocks stay shown in the invisible mode, 0 for blocks vanishing on lock
+pub const INVISIBLE_FADE: [u32; 5] = [0, 1, 3, 5, 10];
+//➔ The ultra countdown is highlighted for the last seconds
+const ULTRA_WARNING: Duration = Duration::from_secs(10);
+
+impl GameMode {
+    pub const ALL: [GameMode; 10] = [GameMode::Marathon, GameMode::Sprint, GameMode::Ultra, GameMode::Dig,
+        GameMode::Survival, GameMode::Zen, GameMode::Master, GameMode::Invisible, GameMode::Puzzle, GameMode::Mission];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Marathon => "MARATHON",
+            GameMode::Sprint => "SPRINT",
+            GameMode::Ultra => "ULTRA",
+            GameMode::Dig => "DIG",
+            GameMode::Survival => "SURVIVAL",
+            GameMode::Zen => "ZEN",
+            GameMode::Master => "MASTER",
+            GameMode::Invisible => "INVISIBLE",
+            GameMode::Puzzle => "PUZZLE",
+            GameMode::Mission => "MISSION",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<GameMode> {
+        GameMode::ALL.iter().copied().find(|mode| mode.name() == name)
+    }
+
+    //➔ The mode `step` entries further in `ALL`, wrapping around at both ends
+    pub fn cycle(self, step: i32) -> GameMode {
+        let len = GameMode::ALL.len() as i32;
+        let index = GameMode::ALL.iter().position(|&mode| mode == self).unwrap_or(0) as i32;
+        GameMode::ALL[(index + step).rem_euclid(len) as usize]
+    }
+
+    //➔ Why a game of the mode cannot be saved halfway, if it cannot. The clock of the timed modes
+    //   would not mean much anymore after a break, and the save file only keeps the board and
+    //   pieces of a marathon: not the garbage, undos, fading blocks, puzzle or missions of the others
+    pub fn save_blocker(&self) -> Option<&'static str> {
+        match self {
+            GameMode::Marathon => None,
+            GameMode::Sprint | GameMode::Ultra | GameMode::Survival | GameMode::Master |
+            GameMode::Mission => Some("TIMED MODES CANNOT BE SAVED"),
+            GameMode::Dig | GameMode::Zen | GameMode::Invisible | GameMode::Puzzle => Some("ONLY MARATHONS CAN BE SAVED"),
+        }
+    }
+
+    //➔ Ultra is a score attack, so it uses the richer guideline scoring
+    pub fn scoring(&self) -> Scoring {
+        match self {
+            GameMode::Marathon | GameMode::Sprint | GameMode::Dig | GameMode::Survival | GameMode::Zen |
+            GameMode::Invisible | GameMode::Puzzle | GameMode::Mission => Scoring::Classic,
+            GameMode::Ultra => Scoring::Guideline,
+            GameMode::Master => Scoring::Master,
+        }
+    }
+
+    //➔ Master comes with rules of its own and always starts from level 0
+    pub fn picks_rules(&self) -> bool {
+        *self != GameMode::Master
+    }
+}
+
+//➔ Formats a whole number of seconds as minutes and seconds: 2:00
+fn format_seconds(seconds: u32) -> String {
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+fn fade_name(fade: u32) -> String {
+    if fade == 0 {
+        "VANISH".to_string()
+    } else {
+        format!("FADE {}S", fade)
+    }
+}
+
+fn goal_name(goal: u32) -> String {
+    if goal == 0 {
+        "ENDLESS".to_string()
+    } else {
+        format!("{} LINES", goal)
+    }
+}
+
+//➔ The settings of the selected mode shown on the title screen
+pub fn option_labels(setup: &GameSetup) -> Vec<String> {
+    match setup.mode {
+        GameMode::Marathon => vec![format!("GOAL: {}", goal_name(setup.marathon_goal))],
+        GameMode::Sprint => vec![format!("LINES: {}", setup.sprint_lines)],
+        GameMode::Ultra => vec![format!("TIME: {}", format_seconds(setup.ultra_seconds))],
+        GameMode::Dig => vec![
+            format!("GARBAGE: {} LINES", setup.dig_lines),
+            format!("MESSINESS: {}%", setup.dig_messiness),
+        ],
+        GameMode::Survival | GameMode::Master | GameMode::Mission => Vec::new(),
+        GameMode::Zen => {
+            let gravity = if setup.zen_gravity == 0 { "OFF".to_string() } else { format!("LEVEL {}", setup.zen_gravity) };
+            vec![format!("GRAVITY: {}", gravity), format!("UNDO: {}", setup.zen_undo)]
+        },
+        GameMode::Invisible => vec![format!("BLOCKS: {}", fade_name(setup.invisible_fade))],
+        GameMode::Puzzle => {
+            let pack = if setup.puzzle_pack.is_empty() { "NONE".to_string() } else { setup.puzzle_pack.to_uppercase() };
+            let count = puzzle::load_pack(&setup.puzzle_pack).map_or(0, |puzzles| puzzles.len());
+            vec![format!("PACK: {}", pack), format!("PUZZLE: {}/{}", setup.puzzle_index + 1, count)]
+        },
+    }
+}
+
+//➔ Changes the setting number `index` of the selected mode by `step` entries
+pub fn adjust_option(setup: &mut GameSetup, index: usize, step: i32) {
+    match (setup.mode, index) {
+        (GameMode::Marathon, _) => setup.marathon_goal = cycle_value(&MARATHON_GOALS, setup.marathon_goal, step),
+        (GameMode::Sprint, _) => setup.sprint_lines = cycle_value(&SPRINT_LINES, setup.sprint_lines, step),
+        (GameMode::Ultra, _) => setup.ultra_seconds = cycle_value(&ULTRA_SECONDS, setup.ultra_seconds, step),
+        (GameMode::Dig, 0) => setup.dig_lines = cycle_value(&DIG_LINES, setup.dig_lines, step),
+        (GameMode::Dig, _) => setup.dig_messiness = cycle_value(&DIG_MESSINESS, setup.dig_messiness, step),
+        (GameMode::Survival, _) | (GameMode::Master, _) | (GameMode::Mission, _) => {},
+        (GameMode::Zen, 0) => setup.zen_gravity = cycle_value(&ZEN_GRAVITY, setup.zen_gravity, step),
+        (GameMode::Zen, _) => setup.zen_undo = cycle_value(&ZEN_UNDO, setup.zen_undo, step),
+        (GameMode::Invisible, _) => setup.invisible_fade = cycle_value(&INVISIBLE_FADE, setup.invisible_fade, step),
+        //➔ another pack starts from its first puzzle
+        (GameMode::Puzzle, 0) => {
+            let packs = puzzle::pack_names();
+            if !packs.is_empty() {
+                let index = packs.iter().position(|pack| *pack == setup.puzzle_pack).unwrap_or(0) as i32;
+                setup.puzzle_pack = packs[(index + step).rem_euclid(packs.len() as i32) as usize].clone();
+                setup.puzzle_index = 0;
+            }
+        },
+        (GameMode::Puzzle, _) => {
+            if let Ok(puzzles) = puzzle::load_pack(&setup.puzzle_pack) {
+                if !puzzles.is_empty() {
+                    setup.puzzle_index = (setup.puzzle_index as i32 + step).rem_euclid(puzzles.len() as i32) as usize;
+                }
+            }
+        },
+    }
+}
+
+//➔ The entry `step` places further in `values`, or the first one if `value` is not in there
+pub fn cycle_value(values: &[u32], value: u32, step: i32) -> u32 {
+    let index = values.iter().position(|&entry| entry == value).unwrap_or(0) as i32;
+    values[(index + step).rem_euclid(values.len() as i32) as usize]
+}
+
+//➔ Lines between two splits of a sprint
+const SPLIT_LINES: usize = 10;
+
+//➔ Name of the board in the records file and whether lower values rank better.
+//   Marathons are ranked separately for every goal and start level, zen is not ranked.
+//   Games by the NES rules and games of every variant have boards of their own, and so does
+//   the daily challenge of every day
+pub fn leaderboard(setup: &GameSetup) -> Option<(String, bool)> {
+    let (board, lower_is_better) = match setup.mode {
+        GameMode::Marathon => Some((format!("marathon{}_{}", setup.marathon_goal, setup.start_level), false)),
+        GameMode::Sprint => Some((format!("sprint{}", setup.sprint_lines), true)),
+        GameMode::Ultra => Some((format!("ultra{}", setup.ultra_seconds), false)),
+        GameMode::Dig => Some((format!("dig{}_{}", setup.dig_lines, setup.dig_messiness), true)),
+        GameMode::Survival => Some((format!("survival{}", setup.start_level), false)),
+        GameMode::Zen => None,
+        GameMode::Master => Some(("master".to_string(), false)),
+        GameMode::Invisible => Some((format!("invisible{}_{}", setup.invisible_fade, setup.start_level), false)),
+        GameMode::Puzzle => None,
+        GameMode::Mission => Some(("mission".to_string(), false)),
+    }?;
+    let board = match setup.rules() {
+        Ruleset::Standard | Ruleset::Ars => board,
+        Ruleset::Nes => format!("{}_nes", board),
+    };
+    if let Some(day) = setup.daily {
+        return Some((format!("daily{}", day), lower_is_better));
+    }
+    match setup.variant {
+        Variant::Normal => Some((board, lower_is_better)),
+        variant => Some((format!("{}_{}", board, variant.file_name()), lower_is_better)),
+    }
+}
+
+//➔ Title of the leaderboard of the mode
+pub fn leaderboard_title(setup: &GameSetup) -> String {
+    if let Some(day) = setup.daily {
+        return format!("DAILY {}", daily::date_text(day));
+    }
+    let title = match setup.mode {
+        GameMode::Marathon => {
+            let goal = if setup.marathon_goal == 0 { "ENDLESS".to_string() } else { setup.marathon_goal.to_string() };
+            format!("MARATHON {} FROM {}", goal, setup.start_level)
+        },
+        GameMode::Sprint => format!("SPRINT {}", setup.sprint_lines),
+        GameMode::Ultra => format!("ULTRA {}", format_seconds(setup.ultra_seconds)),
+        GameMode::Dig => format!("DIG {} {}%", setup.dig_lines, setup.dig_messiness),
+        GameMode::Survival => format!("SURVIVAL FROM {}", setup.start_level),
+        GameMode::Zen => "ZEN".to_string(),
+        GameMode::Master => "MASTER".to_string(),
+        GameMode::Invisible => format!("INVISIBLE {} FROM {}", fade_name(setup.invisible_fade), setup.start_level),
+        GameMode::Puzzle => "PUZZLE".to_string(),
+        GameMode::Mission => "MISSION".to_string(),
+    };
+    let title = match setup.rules() {
+        Ruleset::Standard | Ruleset::Ars => title,
+        Ruleset::Nes => format!("{} NES", title),
+    };
+    match setup.variant {
+        Variant::Normal => title,
+        variant => format!("{} {}", title, variant.name()),
+    }
+}
+
+//➔ How a record of the mode reads on its leaderboard
+pub fn record_text(setup: &GameSetup, record: &Record) -> String {
+    match setup.mode {
+        GameMode::Marathon | GameMode::Ultra | GameMode::Invisible => {
+            let lines = record.details.first().copied().unwrap_or_default();
+            format!("{:<8} {:>4} LINES", record.value, lines)
+        },
+        GameMode::Sprint => format_time_millis(Duration::from_millis(record.value)),
+        GameMode::Survival => {
+            let lines = record.details.first().copied().unwrap_or_default();
+            format!("{} {:>4} LINES", format_time_millis(Duration::from_millis(record.value)), lines)
+        },
+        GameMode::Zen | GameMode::Puzzle => record.value.to_string(),
+        GameMode::Master => {
+            let level = record.details.first().copied().unwrap_or_default();
+            let time = Duration::from_millis(record.details.get(1).copied().unwrap_or_default());
+            let grade = master::grade_name(record.details.get(2).copied().unwrap_or_default() as usize);
+            format!("{:<3} {:>3} {}", grade, level, format_time(time))
+        },
+        GameMode::Dig => {
+            let pieces = record.details.first().copied().unwrap_or_default();
+            format!("{} {:>4} PIECES", format_time_millis(Duration::from_millis(record.value)), pieces)
+        },
+        GameMode::Mission => {
+            let time = Duration::from_millis(record.details.first().copied().unwrap_or_default());
+            format!("{:>3} MISSIONS {}", record.value, format_time(time))
+        },
+    }
+}
+
+//➔ The best result of the mode so far, to compare the game against
+pub fn personal_best(setup: &GameSetup) -> Option<Record> {
+    let (board, lower_is_better) = leaderboard(setup)?;
+    records::load_board(&board, lower_is_better).into_iter().next()
+}
+
+//➔ Sprint times since the first input, taken every SPLIT_LINES lines up to the goal
+fn splits(setup: &GameSetup, engine: &Engine) -> Vec<Duration> {
+    let start = engine.first_input.unwrap_or_default();
+    engine.line_times.iter()
+        .take(setup.sprint_lines as usize)
+        .skip(SPLIT_LINES - 1)
+        .step_by(SPLIT_LINES)
+        .map(|&time| time.saturating_sub(start))
+        .collect()
+}
+
+//➔ Entries the mode adds to the HUD, `best` being the personal best
+pub fn hud_entries(setup: &GameSetup, engine: &Engine, best: Option<&Record>) -> Vec<HudEntry> {
+    let mut entries = Vec::new();
+    if let Some(left) = engine.time_left() {
+        entries.push(HudEntry {
+            label: "TIME LEFT".to_string(),
+            value: format_time_millis(left),
+            warning: left <= ULTRA_WARNING,
+        });
+        if let Some(best) = best {
+            entries.push(HudEntry {
+                label: "BEST".to_string(),
+                value: best.value.to_string(),
+                warning: false,
+            });
+        }
+    }
+    if let Some(goal) = engine.line_goal {
+        entries.push(HudEntry {
+            label: "LINES LEFT".to_string(),
+            value: goal.saturating_sub(engine.tetris.num_lines).to_string(),
+            warning: false,
+        });
+    }
+    if let Some(left) = engine.time_to_rise() {
+        entries.push(HudEntry {
+            label: "NEXT ROW".to_string(),
+            value: format!("{}.{}", left.as_secs(), left.subsec_millis() / 100),
+            warning: left <= RISE_WARNING,
+        });
+    }
+    if let Some(ref zen) = engine.zen {
+        entries.push(HudEntry {
+            label: "UNDO".to_string(),
+            value: zen.undos_left().to_string(),
+            warning: false,
+        });
+    }
+    if let Some(goal) = engine.garbage_goal {
+        entries.push(HudEntry {
+            label: "GARBAGE LEFT".to_string(),
+            value: goal.saturating_sub(engine.tetris.garbage_cleared).to_string(),
+            warning: false,
+        });
+    }
+    if let Some(ref master) = engine.master {
+        let grade = master.grade(&engine.tetris);
+        entries.push(HudEntry {
+            label: "GRADE".to_string(),
+            value: master::grade_name(grade).to_string(),
+            warning: false,
+        });
+        if let Some(score) = master::next_grade_score(grade) {
+            entries.push(HudEntry {
+                label: "NEXT GRADE".to_string(),
+                value: score.to_string(),
+                warning: false,
+            });
+        }
+        entries.push(HudEntry {
+            label: format!("SECTION {}", engine.tetris.current_level / SECTION_LEVELS + 1),
+            value: format_time(master.section_time(engine.time)),
+            warning: false,
+        });
+    }
+    if let Some(goal) = engine.puzzle_goal {
+        entries.push(HudEntry {
+            label: "GOAL".to_string(),
+            value: goal.text(),
+            warning: false,
+        });
+        let pieces_left = engine.tetris.next_kinds.len() + engine.tetris.current_piece.is_some() as usize;
+        entries.push(HudEntry {
+            label: "PIECES LEFT".to_string(),
+            value: pieces_left.to_string(),
+            warning: pieces_left <= 1,
+        });
+    }
+    if let Some(ref missions) = engine.missions {
+        if let Some(mission) = missions.current() {
+            entries.push(HudEntry {
+                label: format!("MISSION {}/{}", missions.index + 1, missions.count()),
+                value: format!("{} {}", mission.count, mission.clear.name()),
+                warning: false,
+            });
+            entries.push(HudEntry {
+                label: "DONE".to_string(),
+                value: format!("{}/{}", missions.progress.min(mission.count), mission.count),
+                warning: false,
+            });
+        }
+        if let Some(left) = missions.time_left(engine.time) {
+            entries.push(HudEntry {
+                label: "TIME LEFT".to_string(),
+                value: format_time_millis(left),
+                warning: left <= ULTRA_WARNING,
+            });
+        }
+        if let Some(left) = missions.pieces_left(&engine.tetris) {
+            entries.push(HudEntry {
+                label: "PIECES LEFT".to_string(),
+                value: left.to_string(),
+                warning: left <= 1,
+            });
+        }
+    }
+    if setup.mode == GameMode::Sprint {
+        let splits = splits(setup, engine);
+        let best_split = best.and_then(|best| best.details.get(splits.len().wrapping_sub(1)));
+        if let (Some(&split), Some(&best_split)) = (splits.last(), best_split) {
+            let best_split = Duration::from_millis(best_split);
+            entries.push(HudEntry {
+                label: format!("SPLIT {}", splits.len() * SPLIT_LINES),
+                value: format_delta(split, best_split),
+                warning: split > best_split,
+            });
+        }
+    }
+    entries
+}
+
+//➔ Saves `record` on the leaderboard of the mode, returns its rank and the best record from before
+fn save_record(setup: &GameSetup, record: Record) -> (Option<usize>, Option<Record>) {
+    let (board, lower_is_better) = match leaderboard(setup) {
+        Some(leaderboard) => leaderboard,
+        None => return (None, None),
+    };
+    let best = records::load_board(&board, lower_is_better).into_iter().next();
+    //➔ only the first attempt at a daily challenge is ranked
+    if setup.daily.is_some_and(|day| !daily::is_ranked(day)) {
+        return (None, best);
+    }
+    let rank = match records::add_record(&board, lower_is_better, record) {
+        Ok(rank) => rank,
+        Err(e) => {
+            println!("Could not save the result to {}: {}", records::RECORDS_FILE, e);
+            None
+        },
+    };
+    (rank, best)
+}
+
+//➔ Saves the result of a game that reached the goal of its mode, returns the lines to show for it
+pub fn finish(setup: &GameSetup, engine: &Engine) -> Vec<String> {
+    let mut lines = match setup.mode {
+        GameMode::Marathon | GameMode::Invisible => finish_marathon(setup, engine),
+        GameMode::Sprint => finish_sprint(setup, engine),
+        GameMode::Ultra => finish_ultra(setup, engine),
+        GameMode::Dig => finish_dig(setup, engine),
+        GameMode::Survival => finish_survival(setup, engine),
+        GameMode::Zen => Vec::new(),
+        GameMode::Master => finish_master(setup, engine),
+        GameMode::Puzzle => finish_puzzle(setup, engine),
+        GameMode::Mission => finish_mission(setup, engine),
+    };
+    if let Some(day) = setup.daily {
+        let attempt = if daily::is_ranked(day) { "RANKED" } else { "PRACTICE" };
+        lines.insert(0, format!("DAILY {}: {}", daily::date_text(day), attempt));
+    }
+    lines
+}
+
+//➔ Mission runs are ranked by the missions done however they ended, the mission missed is shown
+fn finish_mission(setup: &GameSetup, engine: &Engine) -> Vec<String> {
+    let missions = match engine.missions {
+        Some(ref missions) => missions,
+        None => return Vec::new(),
+    };
+    let record = Record {
+        value: missions.index as u64,
+        details: vec![engine.time.as_millis() as u64],
+    };
+    let (rank, best) = save_record(setup, record);
+
+    let mut lines = vec![format!("MISSIONS DONE: {}/{}", missions.index, missions.count())];
+    if let Some(mission) = missions.current() {
+        lines.push(format!("MISSED: {}", mission.name.to_uppercase()));
+        lines.push(mission.text());
+    }
+    lines.extend(rank_lines(rank, best.map(|best| record_text(setup, &best))));
+    lines
+}
+
+//➔ Puzzles are not ranked, whether they were solved is shown along with the goal
+fn finish_puzzle(setup: &GameSetup, engine: &Engine) -> Vec<String> {
+    let mut lines = Vec::new();
+    if let Ok((puzzle, count)) = setup.puzzle() {
+        lines.push(format!("PUZZLE {}/{}: {}", setup.puzzle_index + 1, count, puzzle.name.to_uppercase()));
+    }
+    if let Some(goal) = engine.puzzle_goal {
+        lines.push(format!("GOAL: {}", goal.text()));
+    }
+    lines.push(format!("PIECES USED: {}", engine.tetris.pieces_placed()));
+    lines
+}
+
+//➔ Master games are ranked by score, which the grade follows, however they ended.
+//   The time of every section is shown along with the grade
+fn finish_master(setup: &GameSetup, engine: &Engine) -> Vec<String> {
+    let master = match engine.master {
+        Some(ref master) => master,
+        None => return Vec::new(),
+    };
+    let grade = master.grade(&engine.tetris);
+    let record = Record {
+        value: engine.tetris.score as u64,
+        details: vec![engine.tetris.current_level as u64, engine.time.as_millis() as u64, grade as u64],
+    };
+    let (rank, best) = save_record(setup, record);
+
+    let mut lines = vec![format!("GRADE: {}", master::grade_name(grade))];
+    let mut start = Duration::ZERO;
+    let section_texts = master.section_times.iter().enumerate().map(|(index, &time)| {
+        let text = format!("{:03} {}", index as u32 * SECTION_LEVELS, format_time(time - start));
+        start = time;
+        text
+    }).collect::<Vec<_>>();
+    for pair in section_texts.chunks(2) {
+        lines.push(pair.join("   "));
+    }
+    lines.extend(rank_lines(rank, best.map(|best| record_text(setup, &best))));
+    lines
+}
+
+//➔ A survival game only ends by topping out, it is ranked by how long it lasted
+fn finish_survival(setup: &GameSetup, engine: &Engine) -> Vec<String> {
+    let record = Record {
+        value: engine.time.as_millis() as u64,
+        details: vec![engine.tetris.num_lines as u64],
+    };
+    let (rank, best) = save_record(setup, record);
+    rank_lines(rank, best.map(|best| format_time_millis(Duration::from_millis(best.value))))
+}
+
+fn finish_dig(setup: &GameSetup, engine: &Engine) -> Vec<String> {
+    let time = engine.run_time();
+    let pieces = engine.tetris.pieces_placed();
+    let record = Record {
+        value: time.as_millis() as u64,
+        details: vec![pieces as u64],
+    };
+    let (rank, best) = save_record(setup, record);
+
+    let mut lines = vec![
+        format!("{} GARBAGE LINES: {}", setup.dig_lines, format_time_millis(time)),
+        format!("PIECES: {}", pieces),
+    ];
+    let best_time = best.map(|best| {
+        let best = Duration::from_millis(best.value);
+        format!("{} ({})", format_time_millis(best), format_delta(time, best))
+    });
+    lines.extend(rank_lines(rank, best_time));
+    lines
+}
+
+//➔ Marathons are saved whether they reached their goal or topped out, the summary of
+//   the game is shown along with these lines. Invisible games are ranked the same way
+fn finish_marathon(setup: &GameSetup, engine: &Engine) -> Vec<String> {
+    let record = Record {
+        value: engine.tetris.score as u64,
+        details: vec![engine.tetris.num_lines as u64, engine.tetris.current_level as u64],
+    };
+    let (rank, best) = save_record(setup, record);
+    rank_lines(rank, best.map(|best| best.value.to_string()))
+}
+
+//➔ Tells whether the result is a new best or where it ranked, `best` being the best result from before
+fn rank_lines(rank: Option<usize>, best: Option<String>) -> Vec<String> {
+    let mut lines = Vec::new();
+    match (rank, best) {
+        (Some(0), _) => lines.push("NEW PERSONAL BEST".to_string()),
+        (rank, Some(best)) => {
+            lines.push(format!("BEST: {}", best));
+            if let Some(rank) = rank {
+                lines.push(format!("RANK {}", rank + 1));
+            }
+        },
+        (_, None) => {},
+    }
+    lines
+}
+
+fn finish_ultra(setup: &GameSetup, engine: &Engine) -> Vec<String> {
+    let score = engine.tetris.score as u64;
+    let record = Record {
+        value: score,
+        details: vec![engine.tetris.num_lines as u64],
+    };
+    let (rank, best) = save_record(setup, record);
+
+    let mut lines = vec![
+        format!("SCORE: {}", score),
+        format!("LINES: {}", engine.tetris.num_lines),
+    ];
+    lines.extend(rank_lines(rank, best.map(|best| best.value.to_string())));
+    lines
+}
+
+fn finish_sprint(setup: &GameSetup, engine: &Engine) -> Vec<String> {
+    let time = engine.run_time();
+    let splits = splits(setup, engine);
+    let record = Record {
+        value: time.as_millis() as u64,
+        details: splits.iter().map(|split| split.as_millis() as u64).collect(),
+    };
+    let (rank, best) = save_record(setup, record);
+
+    let mut lines = vec![format!("{} LINES: {}", setup.sprint_lines, format_time_millis(time))];
+    let best_time = best.as_ref().map(|best| {
+        let best = Duration::from_millis(best.value);
+        format!("{} ({})", format_time_millis(best), format_delta(time, best))
+    });
+    lines.extend(rank_lines(rank, best_time));
+    //➔ two splits per line, each against the same split of the previous best
+    let best_splits = best.map(|best| best.details).unwrap_or_default();
+    let split_texts = splits.iter().enumerate().map(|(index, &split)| {
+        let delta = best_splits.get(index).map_or(String::new(), |&best| format_delta(split, Duration::from_millis(best)));
+        format!("{:>3} {} {:>7}", (index + 1) * SPLIT_LINES, format_time_millis(split), delta)
+    }).collect::<Vec<_>>();
+    for pair in split_texts.chunks(2) {
+        lines.push(pair.join("   "));
+    }
+    lines
+}
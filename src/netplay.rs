@@ -0,0 +1,655 @@
+//➔ Network versus: two machines playing a versus game against each other over TCP.
+//
+// One player hosts with `--host [port]` or HOST VERSUS on the title screen, the
+// other connects to them with `--connect address[:port]` or JOIN VERSUS; both
+// can run on the same machine through 127.0.0.1. Every machine runs both boards, so the engines are kept in
+// lockstep: the game goes in ticks of a fixed length, each player sends the
+// actions they pressed during a tick, and a tick is only played once the
+// actions of both players for it are in. The host deals the game, sending the
+// seed and the attack rules, and each player sends their handling so the other
+// machine moves their pieces the same way.
+//
+// Both machines also send the garbage their own board sends and, every so
+// often, a snapshot of it, which the other machine checks against its copy of
+// that board to tell when the games went apart. A player leaving, closing the
+// connection or staying silent for too long leaves the game to the other one.
+// A machine finding the games went apart, or getting a message it cannot make
+// sense of, ends the game with no winner on both machines.
+//
+// The protocol is made of text lines:
+//
+//   hello 1 167 33 6        protocol version and handling (DAS, ARR, SDF), sent by both
+//   seed 123                the host deals the game: the seed of the pieces,
+//   ruleset STANDARD        the ruleset, the start level
+//   level 1
+//   rules clear 0 0 1 2 4   and the lines of its attack file
+//   start                   the end of the game the host deals
+//   action hard_drop 1      an action pressed (1) or released (0) during the tick being sent
+//   tick 42                 the end of tick 42, its actions came before
+//   garbage 42 4            the board of the sender sent 4 lines during tick 42
+//   board 60 0000111...     the blocks of the board of the sender after tick 60, row by row
+//   quit                    the sender left the game
+//   mismatch                the sender found the games went apart, no one wins
+
+use crate::attack::AttackRules;
+use crate::engine::Engine;
+use crate::font::{draw_text_centered, text_height};
+use crate::game::{GameEnd, GameSetup};
+use crate::gamepad::Gamepads;
+use crate::graphics::Graphics;
+use crate::handling::Handling;
+use crate::input::{DeviceInput, InputSource, TimedAction};
+use crate::ruleset::Ruleset;
+use crate::save::{invalid, parse_value};
+use crate::screens;
+use crate::settings::{Action, Settings};
+use crate::versus::{self, exchange_garbage, start_versus};
+use crate::{save_lines_sent, Tetris, TETRIS_HEIGHT};
+
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::pixels::Color;
+
+use std::collections::VecDeque;
+use std::error::Error;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const PROTOCOL_VERSION: u32 = 1;
+pub const DEFAULT_PORT: u16 = 7878;
+//➔ Engine time a tick lasts, about a frame
+const TICK: Duration = Duration::from_millis(16);
+//➔ Ticks a player can send ahead of the ones played, hiding the time messages take to arrive
+const INPUT_DELAY: u32 = 3;
+//➔ Ticks between two snapshots of a board
+const SNAPSHOT_TICKS: u32 = 60;
+//➔ How long the other player can stay silent before they are taken for gone
+const TIMEOUT: Duration = Duration::from_secs(10);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const TEXT_SCALE: u32 = 3;
+
+//➔ Which side of the connection this machine is on
+pub enum Role {
+    //➔ waits for the other player on this port
+    Host(u16),
+    //➔ connects to the player hosting at this address
+    Join(String),
+}
+
+//➔ The role asked for on the command line, if any: `--host [port]` or `--connect address`
+pub fn role_from_args(args: &[String]) -> Option<Role> {
+    let value_after = |flag: &str| args.iter().position(|arg| arg == flag).map(|index| args.get(index + 1));
+    if let Some(port) = value_after("--host") {
+        let port = match port.map(|port| port.parse::<u16>()) {
+            Some(Ok(port)) => port,
+            Some(Err(_)) => {
+                println!("Not a port: {}, hosting on {}", port.map_or("", |port| port.as_str()), DEFAULT_PORT);
+                DEFAULT_PORT
+            },
+            None => DEFAULT_PORT,
+        };
+        return Some(Role::Host(port));
+    }
+    match value_after("--connect") {
+        Some(Some(address)) => Some(Role::Join(address.clone())),
+        Some(None) => {
+            println!("`--connect` needs the address of the host");
+            None
+        },
+        None => None,
+    }
+}
+
+//➔ The connection to the other player, read without blocking the game
+struct Peer {
+    stream: TcpStream,
+    //➔ bytes received that do not make a whole line yet
+    buffer: Vec<u8>,
+    lines: VecDeque<String>,
+    last_heard: Instant,
+}
+
+impl Peer {
+    fn new(stream: TcpStream) -> io::Result<Peer> {
+        stream.set_nodelay(true)?;
+        //➔ reading waits at most a millisecond, writing still waits for the whole message to go out
+        stream.set_read_timeout(Some(Duration::from_millis(1)))?;
+        Ok(Peer {
+            stream,
+            buffer: Vec::new(),
+            lines: VecDeque::new(),
+            last_heard: Instant::now(),
+        })
+    }
+
+    fn send(&mut self, message: &str) -> io::Result<()> {
+        self.stream.write_all(format!("{}\n", message).as_bytes())
+    }
+
+    //➔ Reads what arrived so far, fails once the connection is closed or silent for too long
+    fn receive(&mut self) -> io::Result<()> {
+        let mut bytes = [0; 4096];
+        loop {
+            match self.stream.read(&mut bytes) {
+                Ok(0) => return Err(io::Error::new(ErrorKind::UnexpectedEof, "the connection was closed")),
+                Ok(count) => {
+                    self.buffer.extend_from_slice(&bytes[..count]);
+                    self.last_heard = Instant::now();
+                },
+                Err(e) if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {},
+                Err(e) => return Err(e),
+            }
+        }
+        while let Some(end) = self.buffer.iter().position(|&byte| byte == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            self.lines.push_back(String::from_utf8_lossy(&line).trim().to_string());
+        }
+        if self.last_heard.elapsed() > TIMEOUT {
+            return Err(io::Error::new(ErrorKind::TimedOut, "the other player stopped answering"));
+        }
+        Ok(())
+    }
+
+    //➔ The next line, waiting for it until the timeout, for while the game is being set up
+    fn wait_line(&mut self) -> io::Result<String> {
+        loop {
+            if let Some(line) = self.lines.pop_front() {
+                return Ok(line);
+            }
+            self.receive()?;
+        }
+    }
+}
+
+fn hello(handling: &Handling) -> String {
+    format!("hello {} {} {} {}", PROTOCOL_VERSION, handling.das.as_millis(), handling.arr.as_millis(), handling.sdf)
+}
+
+//➔ The handling of the other player, from their hello
+fn read_hello(line: &str) -> io::Result<Handling> {
+    let mut parts = line.split_whitespace();
+    if parts.next() != Some("hello") {
+        return Err(invalid(format!("expected a hello, got `{}`", line)));
+    }
+    let version: u32 = parse_value("hello", parts.next())?;
+    if version != PROTOCOL_VERSION {
+        return Err(invalid(format!("the other player speaks version {}, this game version {}", version, PROTOCOL_VERSION)));
+    }
+    Ok(Handling {
+        das: Duration::from_millis(parse_value("hello", parts.next())?),
+        arr: Duration::from_millis(parse_value("hello", parts.next())?),
+        sdf: parse_value::<u32>("hello", parts.next())?.max(1),
+    })
+}
+
+//➔ What both machines agreed on before the game starts
+struct NetGame {
+    peer: Peer,
+    //➔ the board of this machine: 0 for the host, 1 for the other player
+    local: usize,
+    handlings: [Handling; 2],
+    setup: GameSetup,
+    seed: u64,
+    rules: AttackRules,
+}
+
+//➔ The host deals the game from its setup and attack rules, and sends it along
+fn host_match(stream: TcpStream, setup: &GameSetup, handling: Handling, rules: AttackRules) -> io::Result<NetGame> {
+    let mut peer = Peer::new(stream)?;
+    let remote = read_hello(&peer.wait_line()?)?;
+    let seed = setup.seed.unwrap_or_else(rand::random::<u64>);
+    peer.send(&hello(&handling))?;
+    peer.send(&format!("seed {}", seed))?;
+    peer.send(&format!("ruleset {}", setup.ruleset.name()))?;
+    peer.send(&format!("level {}", setup.start_level))?;
+    for line in rules.text().lines() {
+        peer.send(&format!("rules {}", line))?;
+    }
+    peer.send("start")?;
+    Ok(NetGame {
+        peer,
+        local: 0,
+        handlings: [handling, remote],
+        setup: setup.clone(),
+        seed,
+        rules,
+    })
+}
+
+fn join_match(stream: TcpStream, setup: &GameSetup, handling: Handling) -> io::Result<NetGame> {
+    let mut peer = Peer::new(stream)?;
+    peer.send(&hello(&handling))?;
+    let remote = read_hello(&peer.wait_line()?)?;
+    let mut setup = setup.clone();
+    let mut seed = None;
+    let mut rules = String::new();
+    loop {
+        let line = peer.wait_line()?;
+        let mut parts = line.split_whitespace();
+        let key = parts.next().unwrap_or_default();
+        match key {
+            "seed" => seed = Some(parse_value(key, parts.next())?),
+            "ruleset" => {
+                let name: String = parse_value(key, parts.next())?;
+                setup.ruleset = Ruleset::from_name(&name).ok_or_else(|| invalid(format!("unknown ruleset `{}`", name)))?;
+            },
+            "level" => setup.start_level = parse_value(key, parts.next())?,
+            "rules" => {
+                rules.push_str(&parts.collect::<Vec<_>>().join(" "));
+                rules.push('\n');
+            },
+            "start" => break,
+            _ => return Err(invalid(format!("unexpected `{}` while the game is dealt", line))),
+        }
+    }
+    Ok(NetGame {
+        peer,
+        local: 1,
+        handlings: [remote, handling],
+        setup,
+        seed: seed.ok_or_else(|| invalid("the host sent no seed".to_string()))?,
+        rules: AttackRules::parse(&rules)?,
+    })
+}
+
+fn draw_message(graphics: &mut Graphics, lines: &[&str]) -> Result<(), String> {
+    graphics.clear();
+    let center_x = graphics.width as i32 / 2;
+    let mut y = graphics.grid_y + TETRIS_HEIGHT as i32 * 4;
+    for line in lines {
+        draw_text_centered(&mut graphics.canvas, line, center_x, y, TEXT_SCALE, Color::RGB(255, 255, 255))?;
+        y += text_height(TEXT_SCALE) as i32 * 2;
+    }
+    graphics.present();
+    Ok(())
+}
+
+//➔ Waits for the other player to connect, until they do or the player gives up,
+//   going back to the title screen or closing the window
+fn wait_for_player(graphics: &mut Graphics, event_pump: &mut sdl2::EventPump, listener: &TcpListener, port: u16)
+    -> Result<Result<TcpStream, GameEnd>, Box<dyn Error>> {
+    listener.set_nonblocking(true)?;
+    let waiting = format!("PORT {}", port);
+    loop {
+        for event in event_pump.poll_iter() {
+            match event {
+                Event::Quit {..} => return Ok(Err(GameEnd::Exit)),
+                Event::KeyDown {keycode: Some(Keycode::Escape), ..} => return Ok(Err(GameEnd::Menu)),
+                _ => {},
+            }
+        }
+        match listener.accept() {
+            Ok((stream, _)) => {
+                stream.set_nonblocking(false)?;
+                return Ok(Ok(stream));
+            },
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {},
+            Err(e) => return Err(e.into()),
+        }
+        draw_message(graphics, &["WAITING FOR A PLAYER", &waiting, "ESCAPE TO CANCEL"])?;
+        thread::sleep(Duration::new(0, 1_000_000_000u32 / 60));
+    }
+}
+
+fn connect(address: &str) -> io::Result<TcpStream> {
+    //➔ the port can be left out, the host uses the default one unless told otherwise
+    let address = if address.contains(':') { address.to_string() } else { format!("{}:{}", address, DEFAULT_PORT) };
+    let mut last_error = invalid(format!("no address found for `{}`", address));
+    for address in address.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&address, CONNECT_TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = e,
+        }
+    }
+    Err(last_error)
+}
+
+//➔ A message of the other player during the game
+enum Message {
+    Action(Action, bool),
+    Tick(u32),
+    //➔ a garbage event or a snapshot, checked against the copy of their board as is
+    Check(String),
+    Quit,
+    Mismatch,
+}
+
+fn read_message(line: &str) -> io::Result<Message> {
+    let mut parts = line.split_whitespace();
+    let key = parts.next().unwrap_or_default();
+    match key {
+        "action" => {
+            let name: String = parse_value(key, parts.next())?;
+            let action = Action::from_name(&name).ok_or_else(|| invalid(format!("unknown action `{}`", name)))?;
+            Ok(Message::Action(action, parse_value::<u32>(key, parts.next())? != 0))
+        },
+        "tick" => Ok(Message::Tick(parse_value(key, parts.next())?)),
+        "garbage" | "board" => Ok(Message::Check(line.to_string())),
+        "quit" => Ok(Message::Quit),
+        "mismatch" => Ok(Message::Mismatch),
+        _ => Err(invalid(format!("unexpected `{}` during the game", line))),
+    }
+}
+
+fn garbage_message(tick: u32, lines: u32) -> String {
+    format!("garbage {} {}", tick, lines)
+}
+
+fn board_message(tick: u32, tetris: &Tetris) -> String {
+    let rows = tetris.game_map.iter().map(|row| {
+        row.iter().map(|&block| char::from_digit(block as u32, 36).unwrap_or('?')).collect::<String>()
+    }).collect::<Vec<_>>();
+    format!("board {} {}", tick, rows.join("/"))
+}
+
+//➔ How a network game ended
+enum Finish {
+    //➔ a board topped out: the game is over the same way on both machines
+    Over,
+    //➔ the other player left, or the connection was lost: why, as the player is told
+    Left(String),
+    //➔ the boards of the two machines are not the same anymore, or a machine got a message
+    //   it cannot make sense of: what went wrong, as the player is told
+    OutOfSync(String),
+    //➔ this player left the game, or closed the window
+    Quit { window_open: bool },
+}
+
+//➔ The title and lines of the screen telling this player how a game they did not leave ended
+fn outcome(finish: &Finish, engines: &[Engine; 2], local: usize) -> (&'static str, Vec<String>) {
+    match finish {
+        Finish::Left(why) => ("YOU WIN", vec![why.to_uppercase()]),
+        Finish::OutOfSync(why) => ("NO CONTEST", vec![why.to_uppercase()]),
+        Finish::Over | Finish::Quit {..} => {
+            let title = match (engines[local].topped_out, engines[1 - local].topped_out) {
+                (true, true) => "DRAW",
+                (true, false) => "YOU LOSE",
+                _ => "YOU WIN",
+            };
+            (title, Vec::new())
+        },
+    }
+}
+
+//➔ Hosts or joins a network versus game, plays it and shows how it ended
+pub fn run_network(graphics: &mut Graphics, event_pump: &mut sdl2::EventPump, gamepads: &mut Gamepads,
+    settings: &Settings, setup: &GameSetup, role: &Role) -> Result<GameEnd, Box<dyn Error>> {
+    let handling = Handling::from_settings(settings);
+    let connected = match *role {
+        //➔ a port already in use is told like any other connection failure
+        Role::Host(port) => match TcpListener::bind(("0.0.0.0", port)) {
+            Ok(listener) => match wait_for_player(graphics, event_pump, &listener, port)? {
+                Ok(stream) => host_match(stream, setup, handling, AttackRules::load()),
+                Err(end) => return Ok(end),
+            },
+            Err(e) => Err(e),
+        },
+        Role::Join(ref address) => {
+            draw_message(graphics, &["CONNECTING TO", address])?;
+            connect(address).and_then(|stream| join_match(stream, setup, handling))
+        },
+    };
+    let mut game = match connected {
+        Ok(game) => game,
+        Err(e) => {
+            println!("Could not start the network game: {}", e);
+            let window_open = screens::message_screen(graphics, event_pump, "NO GAME", &[e.to_string().to_uppercase()]);
+            return Ok(if window_open { GameEnd::Menu } else { GameEnd::Exit });
+        },
+    };
+    let mut engines = start_versus(&game.setup, Some(game.seed), &game.rules);
+    let finish = play_network(graphics, event_pump, gamepads, settings, &mut game, &mut engines);
+    graphics.place_board(None);
+    let (title, mut lines) = match finish? {
+        Finish::Quit { window_open: false } => return Ok(GameEnd::Exit),
+        Finish::Quit { window_open: true } => return Ok(GameEnd::Menu),
+        finish => outcome(&finish, &engines, game.local),
+    };
+    //➔ only the lines sent from this machine go into its highscore file
+    let local = &engines[game.local];
+    let sent = local.attack.as_ref().map_or(0, |attack| attack.lines_sent);
+    let record = if save_lines_sent(sent) { " NEW RECORD" } else { "" };
+    lines.push(format!("SENT {} LINES {}{}", sent, local.tetris.num_lines, record));
+    let window_open = screens::message_screen(graphics, event_pump, title, &lines);
+    Ok(if window_open { GameEnd::Menu } else { GameEnd::Exit })
+}
+
+//➔ Ends the game with no winner on both machines, telling the other one why it ends
+fn mismatch(peer: &mut Peer, why: String) -> Finish {
+    let _ = peer.send("mismatch");
+    Finish::OutOfSync(why)
+}
+
+//➔ The ticks of both players on their way to the boards, the same way on both machines
+#[derive(Default)]
+struct Lockstep {
+    //➔ actions pressed since the last tick sent
+    pressed: Vec<(Action, bool)>,
+    //➔ the actions of the ticks sent or received and not played yet, for each board
+    ticks: [VecDeque<Vec<(Action, bool)>>; 2],
+    //➔ actions of the other player for the tick they have not ended yet
+    received: Vec<(Action, bool)>,
+    next_tick: u32,
+    received_ticks: u32,
+    played: u32,
+    //➔ what the other machine says about its board, and what the copy of that board here says
+    reported: VecDeque<String>,
+    expected: VecDeque<String>,
+}
+
+impl Lockstep {
+    //➔ Sends the tick of this player once it is its time, reads what the other player sent and
+    //   plays every tick both players sent on both boards. Returns how the game ended, if it did
+    fn step(&mut self, game: &mut NetGame, engines: &mut [Engine; 2]) -> Option<Finish> {
+        let local = game.local;
+        let remote = 1 - local;
+        let peer = &mut game.peer;
+        if self.next_tick < self.played + INPUT_DELAY {
+            let mut sent = Ok(());
+            for &(action, down) in self.pressed.iter() {
+                sent = sent.and_then(|_| peer.send(&format!("action {} {}", action.name(), down as u32)));
+            }
+            if let Err(e) = sent.and_then(|_| peer.send(&format!("tick {}", self.next_tick))) {
+                return Some(Finish::Left(e.to_string()));
+            }
+            self.ticks[local].push_back(std::mem::take(&mut self.pressed));
+            self.next_tick += 1;
+        }
+
+        if let Err(e) = peer.receive() {
+            return Some(Finish::Left(e.to_string()));
+        }
+        while let Some(line) = peer.lines.pop_front() {
+            match read_message(&line) {
+                Ok(Message::Action(action, down)) => self.received.push((action, down)),
+                Ok(Message::Tick(tick)) if tick == self.received_ticks => {
+                    self.ticks[remote].push_back(std::mem::take(&mut self.received));
+                    self.received_ticks += 1;
+                },
+                Ok(Message::Tick(tick)) => {
+                    return Some(mismatch(peer, format!("got tick {} instead of {}", tick, self.received_ticks)));
+                },
+                Ok(Message::Check(line)) => self.reported.push_back(line),
+                Ok(Message::Quit) => return Some(Finish::Left("the other player left".to_string())),
+                Ok(Message::Mismatch) => {
+                    return Some(Finish::OutOfSync("the other machine found the games went apart".to_string()));
+                },
+                Err(e) => return Some(mismatch(peer, e.to_string())),
+            }
+        }
+
+        //➔ every tick both players sent is played on both boards, the same way on both machines
+        while !self.ticks[0].is_empty() && !self.ticks[1].is_empty() {
+            let played = self.played;
+            let start = TICK * played;
+            for (player, engine) in engines.iter_mut().enumerate() {
+                let actions = self.ticks[player].pop_front().unwrap_or_default().into_iter()
+                    .map(|(action, pressed)| TimedAction { time: start, action, pressed })
+                    .collect::<Vec<_>>();
+                engine.advance(&actions, start + TICK, &game.handlings[player]);
+            }
+            let sent = exchange_garbage(engines);
+            if sent[local] > 0 {
+                if let Err(e) = peer.send(&garbage_message(played, sent[local])) {
+                    return Some(Finish::Left(e.to_string()));
+                }
+            }
+            if sent[remote] > 0 {
+                self.expected.push_back(garbage_message(played, sent[remote]));
+            }
+            if played.is_multiple_of(SNAPSHOT_TICKS) {
+                if let Err(e) = peer.send(&board_message(played, &engines[local].tetris)) {
+                    return Some(Finish::Left(e.to_string()));
+                }
+                self.expected.push_back(board_message(played, &engines[remote].tetris));
+            }
+            self.played += 1;
+            if engines.iter().any(|engine| engine.topped_out) {
+                return Some(Finish::Over);
+            }
+        }
+        while !self.reported.is_empty() && !self.expected.is_empty() {
+            if self.reported.pop_front() != self.expected.pop_front() {
+                return Some(mismatch(peer, "the games went out of sync".to_string()));
+            }
+        }
+        None
+    }
+}
+
+fn play_network(graphics: &mut Graphics, event_pump: &mut sdl2::EventPump, gamepads: &mut Gamepads,
+    settings: &Settings, game: &mut NetGame, engines: &mut [Engine; 2]) -> Result<Finish, Box<dyn Error>> {
+    let mut input = DeviceInput::default();
+    let mut lockstep = Lockstep::default();
+    let names = if game.local == 0 { ["YOU", "OPPONENT"] } else { ["OPPONENT", "YOU"] };
+
+    loop {
+        for event in event_pump.poll_iter() {
+            match event {
+                Event::Quit {..} => {
+                    let _ = game.peer.send("quit");
+                    return Ok(Finish::Quit { window_open: false });
+                },
+                Event::ControllerDeviceAdded {..} | Event::ControllerDeviceRemoved {..} => gamepads.refresh(),
+                event => input.handle_event(&event, settings, gamepads, Duration::ZERO),
+            }
+        }
+        //➔ the game cannot be paused for both players: pausing leaves it, restarting does nothing
+        for action in input.poll(Duration::ZERO) {
+            match action.action {
+                Action::Pause if action.pressed => {
+                    let _ = game.peer.send("quit");
+                    return Ok(Finish::Quit { window_open: true });
+                },
+                Action::Pause | Action::Restart => {},
+                _ => lockstep.pressed.push((action.action, action.pressed)),
+            }
+        }
+        if let Some(finish) = lockstep.step(game, engines) {
+            return Ok(finish);
+        }
+
+        graphics.clear();
+        versus::draw_versus(graphics, engines, settings, names)?;
+        graphics.present();
+        thread::sleep(Duration::new(0, 1_000_000_000u32 / 60));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn handling(das: u64) -> Handling {
+        Handling { das: Duration::from_millis(das), arr: Duration::from_millis(33), sdf: 6 }
+    }
+
+    //➔ A host dealing `setup` and `rules` and a player joining them, over 127.0.0.1
+    fn connected(setup: GameSetup, rules: AttackRules) -> (NetGame, NetGame) {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).expect("a port should be free");
+        let port = listener.local_addr().expect("the listener should have an address").port();
+        let host = thread::spawn(move || {
+            let (stream, _) = listener.accept().expect("the other player should connect");
+            host_match(stream, &setup, handling(167), rules).expect("the host should deal the game")
+        });
+        let stream = connect(&format!("127.0.0.1:{}", port)).expect("the host should be there");
+        let joined = join_match(stream, &GameSetup::default(), handling(100)).expect("the game should be dealt");
+        (host.join().expect("the host should not panic"), joined)
+    }
+
+    fn is_empty(tetris: &Tetris) -> bool {
+        tetris.game_map.iter().flatten().all(|&block| block == 0)
+    }
+
+    #[test]
+    fn joining_player_gets_the_game_the_host_deals() {
+        let setup = GameSetup { ruleset: Ruleset::Nes, start_level: 5, seed: Some(42), ..GameSetup::default() };
+        let rules = AttackRules { cancel: false, garbage_cap: 4, ..AttackRules::default() };
+        let (host, joined) = connected(setup, rules.clone());
+        assert_eq!((host.local, joined.local), (0, 1));
+        assert_eq!((host.seed, joined.seed), (42, 42));
+        assert_eq!(joined.setup.ruleset, Ruleset::Nes);
+        assert_eq!(joined.setup.start_level, 5);
+        assert_eq!(joined.rules, rules);
+        assert_eq!(host.handlings, [handling(167), handling(100)]);
+        assert_eq!(joined.handlings, host.handlings);
+    }
+
+    #[test]
+    fn both_machines_play_the_same_ticks() {
+        let (mut host, mut joined) = connected(GameSetup { seed: Some(7), ..GameSetup::default() }, AttackRules::default());
+        let mut host_engines = start_versus(&host.setup, Some(host.seed), &host.rules);
+        let mut joined_engines = start_versus(&joined.setup, Some(joined.seed), &joined.rules);
+        let mut host_lockstep = Lockstep::default();
+        let mut joined_lockstep = Lockstep::default();
+        host_lockstep.pressed = vec![(Action::HardDrop, true), (Action::HardDrop, false)];
+        for _ in 0..2000 {
+            assert!(host_lockstep.step(&mut host, &mut host_engines).is_none());
+            assert!(joined_lockstep.step(&mut joined, &mut joined_engines).is_none());
+            if host_lockstep.played >= 30 && joined_lockstep.played >= 30 {
+                break;
+            }
+        }
+        assert!(host_lockstep.played >= 30 && joined_lockstep.played >= 30, "the ticks should have been played");
+        //➔ the hard drop of the host landed on its board on both machines, and on no other board
+        assert!(!is_empty(&host_engines[0].tetris));
+        assert!(is_empty(&host_engines[1].tetris));
+        for player in 0..2 {
+            assert_eq!(host_engines[player].tetris.game_map, joined_engines[player].tetris.game_map);
+        }
+    }
+
+    #[test]
+    fn player_dropping_out_leaves_the_game_to_the_other() {
+        let (mut host, joined) = connected(GameSetup::default(), AttackRules::default());
+        let mut engines = start_versus(&host.setup, Some(host.seed), &host.rules);
+        let mut lockstep = Lockstep::default();
+        drop(joined);
+        let finish = (0..2000).find_map(|_| lockstep.step(&mut host, &mut engines));
+        let finish = finish.expect("the host should notice the other player is gone");
+        assert!(matches!(finish, Finish::Left(_)));
+        assert_eq!(outcome(&finish, &engines, host.local).0, "YOU WIN");
+    }
+
+    #[test]
+    fn bad_tick_ends_the_game_with_no_winner_on_both_machines() {
+        let (mut host, mut joined) = connected(GameSetup::default(), AttackRules::default());
+        let mut host_engines = start_versus(&host.setup, Some(host.seed), &host.rules);
+        let mut joined_engines = start_versus(&joined.setup, Some(joined.seed), &joined.rules);
+        let mut host_lockstep = Lockstep::default();
+        let mut joined_lockstep = Lockstep::default();
+        joined.peer.send("tick 5").expect("the host should be there");
+        let finish = (0..2000).find_map(|_| host_lockstep.step(&mut host, &mut host_engines));
+        let finish = finish.expect("the host should notice the tick out of order");
+        assert!(matches!(finish, Finish::OutOfSync(_)));
+        assert_eq!(outcome(&finish, &host_engines, host.local).0, "NO CONTEST");
+        let finish = (0..2000).find_map(|_| joined_lockstep.step(&mut joined, &mut joined_engines));
+        let finish = finish.expect("the other player should be told the game ends");
+        assert!(matches!(finish, Finish::OutOfSync(_)));
+        assert_eq!(outcome(&finish, &joined_engines, joined.local).0, "NO CONTEST");
+    }
+
+}
@@ -0,0 +1,219 @@
+//➔ Puzzles: a given board, a given list of pieces and a goal to reach with them.
+//
+// Puzzles come in packs, one text file per pack in the puzzle directory. Every
+// puzzle of a pack starts with a `puzzle` line giving its name, followed by
+// `key value...` lines like the other files of the game:
+//
+//   puzzle FIRST TETRIS
+//   pieces T I O
+//   hold off
+//   goal lines 4
+//   row XXXXXXXXX.
+//
+// `pieces` lists the piece letters in the order they come in. `goal` is one of
+// `clear_all`, `lines N`, `tspin_double` and `survive N` (place N pieces). Each
+// `row` line is a row of the board: `.` for an empty cell, a piece letter for a
+// block of that piece and `X` for garbage. The rows given fill the bottom of the
+// board, the last one being the bottom row.
+
+use crate::save::{invalid, parse_value};
+use crate::{read_from_file, BOARD_HEIGHT, BOARD_WIDTH, GARBAGE};
+
+use std::fs;
+use std::io;
+
+pub const PUZZLE_DIR: &str = "puzzles";
+const PUZZLE_EXTENSION: &str = "txt";
+
+//➔ Letters of the piece kinds, in the order of the kinds
+const PIECE_LETTERS: [char; 7] = ['I', 'O', 'T', 'S', 'Z', 'J', 'L'];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Goal {
+    //➔ leave the board empty after a clear
+    ClearAll,
+    Lines(u32),
+    //➔ clear two lines at once with a T that turned into place
+    TSpinDouble,
+    //➔ place that many pieces without topping out
+    Survive(u32),
+}
+
+impl Goal {
+    pub fn text(&self) -> String {
+        match self {
+            Goal::ClearAll => "CLEAR ALL".to_string(),
+            Goal::Lines(lines) => format!("{} LINES", lines),
+            Goal::TSpinDouble => "T-SPIN DOUBLE".to_string(),
+            Goal::Survive(pieces) => format!("SURVIVE {}", pieces),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Puzzle {
+    pub name: String,
+    pub board: Vec<Vec<u8>>,
+    //➔ kinds of the pieces, the first one comes in first
+    pub pieces: Vec<u8>,
+    pub hold: bool,
+    pub goal: Goal,
+}
+
+//➔ Names of the packs in the puzzle directory, in alphabetical order
+pub fn pack_names() -> Vec<String> {
+    let entries = match fs::read_dir(PUZZLE_DIR) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    let mut names = entries.filter_map(|entry| {
+        let path = entry.ok()?.path();
+        if path.extension()? != PUZZLE_EXTENSION {
+            return None;
+        }
+        Some(path.file_stem()?.to_str()?.to_string())
+    }).collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+//➔ The puzzles of the pack `name`, in the order of the file
+pub fn load_pack(name: &str) -> io::Result<Vec<Puzzle>> {
+    parse_pack(&read_from_file(&format!("{}/{}.{}", PUZZLE_DIR, name, PUZZLE_EXTENSION))?)
+}
+
+fn parse_pack(content: &str) -> io::Result<Vec<Puzzle>> {
+    let mut puzzles = Vec::new();
+    let mut current: Option<Puzzle> = None;
+    let mut rows: Vec<Vec<u8>> = Vec::new();
+    for line in content.lines() {
+        let mut parts = line.split_whitespace();
+        let key = match parts.next() {
+            Some(key) => key,
+            None => continue,
+        };
+        if key == "puzzle" {
+            if let Some(puzzle) = current.take() {
+                puzzles.push(finish_puzzle(puzzle, &rows)?);
+            }
+            rows.clear();
+            current = Some(Puzzle {
+                name: parts.collect::<Vec<_>>().join(" "),
+                board: Vec::new(),
+                pieces: Vec::new(),
+                hold: true,
+                goal: Goal::ClearAll,
+            });
+            continue;
+        }
+        let puzzle = match current {
+            Some(ref mut puzzle) => puzzle,
+            None => return Err(invalid(format!("`{}` before the first puzzle", key))),
+        };
+        match key {
+            "pieces" => {
+                for letter in parts.flat_map(|part| part.chars()) {
+                    puzzle.pieces.push(piece_kind(letter)?);
+                }
+            },
+            "hold" => puzzle.hold = parse_value::<String>(key, parts.next())? == "on",
+            "goal" => {
+                let goal: String = parse_value(key, parts.next())?;
+                puzzle.goal = match goal.as_str() {
+                    "clear_all" => Goal::ClearAll,
+                    "lines" => Goal::Lines(parse_value(key, parts.next())?),
+                    "tspin_double" => Goal::TSpinDouble,
+                    "survive" => Goal::Survive(parse_value(key, parts.next())?),
+                    _ => return Err(invalid(format!("unknown goal `{}`", goal))),
+                };
+            },
+            "row" => {
+                let row: String = parse_value(key, parts.next())?;
+                let row = row.chars().map(|cell| match cell {
+                    '.' => Ok(0),
+                    'X' => Ok(GARBAGE),
+                    letter => piece_kind(letter).map(|kind| kind + 1),
+                }).collect::<io::Result<Vec<u8>>>()?;
+                if row.len() != BOARD_WIDTH {
+                    return Err(invalid(format!("rows have {} cells, found {}", BOARD_WIDTH, row.len())));
+                }
+                rows.push(row);
+            },
+            _ => {},
+        }
+    }
+    if let Some(puzzle) = current {
+        puzzles.push(finish_puzzle(puzzle, &rows)?);
+    }
+    Ok(puzzles)
+}
+
+fn piece_kind(letter: char) -> io::Result<u8> {
+    PIECE_LETTERS.iter().position(|&piece| piece == letter)
+        .map(|kind| kind as u8)
+        .ok_or_else(|| invalid(format!("unknown piece `{}`", letter)))
+}
+
+//➔ Puts the rows given at the bottom of an empty board and checks the puzzle can be played
+fn finish_puzzle(mut puzzle: Puzzle, rows: &[Vec<u8>]) -> io::Result<Puzzle> {
+    if rows.len() > BOARD_HEIGHT {
+        return Err(invalid(format!("puzzle `{}` has more than {} rows", puzzle.name, BOARD_HEIGHT)));
+    }
+    if puzzle.pieces.is_empty() {
+        return Err(invalid(format!("puzzle `{}` has no pieces", puzzle.name)));
+    }
+    puzzle.board = vec![vec![0; BOARD_WIDTH]; BOARD_HEIGHT - rows.len()];
+    puzzle.board.extend(rows.iter().cloned());
+    Ok(puzzle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PACK: &str = "puzzle FIRST TETRIS\n\
+        pieces T I\n\
+        hold off\n\
+        goal lines 4\n\
+        row XXXXXXXXX.\n\
+        row SSZZ.XXXXX\n\
+        \n\
+        puzzle SECOND ONE\n\
+        pieces O\n";
+
+    #[test]
+    fn pack_loads_in_the_order_of_the_file() {
+        let puzzles = parse_pack(PACK).expect("the pack should load");
+        assert_eq!(puzzles.len(), 2);
+        let first = &puzzles[0];
+        assert_eq!(first.name, "FIRST TETRIS");
+        assert_eq!(first.pieces, vec![2, 0]);
+        assert!(!first.hold);
+        assert_eq!(first.goal, Goal::Lines(4));
+        assert_eq!(first.board.len(), BOARD_HEIGHT);
+        assert!(first.board[..BOARD_HEIGHT - 2].iter().flatten().all(|&block| block == 0));
+        assert_eq!(first.board[BOARD_HEIGHT - 2], vec![GARBAGE, GARBAGE, GARBAGE, GARBAGE, GARBAGE, GARBAGE, GARBAGE, GARBAGE, GARBAGE, 0]);
+        assert_eq!(first.board[BOARD_HEIGHT - 1], vec![4, 4, 5, 5, 0, GARBAGE, GARBAGE, GARBAGE, GARBAGE, GARBAGE]);
+        let second = &puzzles[1];
+        assert_eq!((second.name.as_str(), second.hold, second.goal), ("SECOND ONE", true, Goal::ClearAll));
+    }
+
+    #[test]
+    fn bundled_pack_loads() {
+        let puzzles = parse_pack(include_str!("../puzzles/basics.txt")).expect("the bundled pack should load");
+        assert!(!puzzles.is_empty());
+    }
+
+    #[test]
+    fn bad_puzzles_are_refused() {
+        assert!(parse_pack("pieces T\n").is_err());
+        assert!(parse_pack("puzzle NO PIECES\ngoal clear_all\n").is_err());
+        assert!(parse_pack("puzzle A\npieces TQ\n").is_err());
+        assert!(parse_pack("puzzle A\npieces T\ngoal win\n").is_err());
+        assert!(parse_pack("puzzle A\npieces T\ngoal lines some\n").is_err());
+        assert!(parse_pack("puzzle A\npieces T\nrow XXXX\n").is_err());
+        assert!(parse_pack("puzzle A\npieces T\nrow XXXXXXXXX?\n").is_err());
+        let too_high = "row XXXXXXXXX.\n".repeat(BOARD_HEIGHT + 1);
+        assert!(parse_pack(&format!("puzzle A\npieces T\n{}", too_high)).is_err());
+    }
+}
@@ -0,0 +1,135 @@
+//➔ Piece randomizer.
+//
+// The generator is a plain xorshift so that its whole state fits in two numbers
+// and can be written into a save file and restored later.
+
+#[derive(Debug, Clone)]
+pub struct Randomizer {
+    state: u64,
+    prev: u8,
+    //➔ the last kinds dealt by `next_kind_tgm`, the newest last
+    history: [u8; 4],
+}
+
+//➔ Rolls the TGM randomizer makes at most to find a kind that is not in its history
+const TGM_ROLLS: u32 = 4;
+//➔ The TGM history starts out as Z, S, Z, S so that neither comes early
+const TGM_HISTORY: [u8; 4] = [4, 3, 4, 3];
+//➔ The first TGM piece is never an S, Z or O: one of I, T, J and L
+const TGM_FIRST_KINDS: [u8; 4] = [0, 2, 5, 6];
+
+impl Randomizer {
+    pub fn from_seed(seed: u64) -> Randomizer {
+        Randomizer {
+            //xorshift gets stuck on zero, so never let the state be zero
+            state: if seed == 0 { 0x9e37_79b9_7f4a_7c15 } else { seed },
+            prev: 7,
+            history: TGM_HISTORY,
+        }
+    }
+
+    pub fn from_state(state: u64, prev: u8) -> Randomizer {
+        let mut randomizer = Randomizer::from_seed(state);
+        randomizer.prev = prev;
+        randomizer
+    }
+
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
+    pub fn prev(&self) -> u8 {
+        self.prev
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.state = x;
+        (x >> 32) as u32
+    }
+
+    //➔ Returns a number in 0..n
+    pub fn next_below(&mut self, n: u32) -> u32 {
+        self.next_u32() % n
+    }
+
+    //➔ Returns a piece kind the NES way: a roll among 8 values where the 8th one or a repeat
+    //   of the previous piece rolls again, the second roll being offset by the previous piece
+    pub fn next_kind_nes(&mut self) -> u8 {
+        let mut kind = self.next_below(8) as u8;
+        if kind == 7 || kind == self.prev {
+            kind = (self.next_below(8) as u8 + self.prev) % 7;
+        }
+        self.prev = kind;
+        kind
+    }
+
+    //➔ Returns a piece kind the TGM way: rolls up to TGM_ROLLS times for a kind that is not
+    //   among the last four dealt, keeping the last roll if none was found
+    pub fn next_kind_tgm(&mut self) -> u8 {
+        let mut kind = 0;
+        for _ in 0..TGM_ROLLS {
+            kind = if self.prev == 7 {
+                TGM_FIRST_KINDS[self.next_below(TGM_FIRST_KINDS.len() as u32) as usize]
+            } else {
+                self.next_below(7) as u8
+            };
+            if !self.history.contains(&kind) {
+                break;
+            }
+        }
+        self.history.rotate_left(1);
+        self.history[3] = kind;
+        self.prev = kind;
+        kind
+    }
+
+    //➔ Returns a piece kind in 0..7, re-rolling once if it repeats the previous one
+    pub fn next_kind(&mut self) -> u8 {
+        let mut kind = (self.next_u32() % 7) as u8;
+        if kind == self.prev {
+            kind = (self.next_u32() % 7) as u8;
+        }
+        self.prev = kind;
+        kind
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_tgm_piece_is_never_an_s_z_or_o() {
+        for seed in 0..200 {
+            assert!(TGM_FIRST_KINDS.contains(&Randomizer::from_seed(seed).next_kind_tgm()), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn tgm_rolls_four_times_against_the_last_four_kinds() {
+        for seed in [1, 42, 12345] {
+            let mut randomizer = Randomizer::from_seed(seed);
+            let first = randomizer.next_kind_tgm();
+            let mut history = TGM_HISTORY.to_vec();
+            history.remove(0);
+            history.push(first);
+            let mut repeats = 0;
+            for _ in 0..2000 {
+                let mut rolls = randomizer.clone();
+                let rolls = (0..TGM_ROLLS).map(|_| rolls.next_below(7) as u8).collect::<Vec<_>>();
+                let expected = rolls.iter().copied().find(|kind| !history.contains(kind)).unwrap_or(rolls[3]);
+                let kind = randomizer.next_kind_tgm();
+                assert_eq!(kind, expected);
+                repeats += history.contains(&kind) as u32;
+                history.remove(0);
+                history.push(kind);
+            }
+            //➔ a kind of the history only comes back when all four rolls hit it
+            assert!(repeats < 200, "{} repeats", repeats);
+        }
+    }
+}
@@ -0,0 +1,82 @@
+//➔ Leaderboards of the modes that do not go into the highscore file.
+//
+// Every line of the records file holds one result: the name of its board, the
+// value it is ranked by and any details kept along with it (e.g. the split
+// times of a sprint). Each board keeps its NUM_HIGHSCORES best results.
+
+use crate::{read_from_file, write_into_file, NUM_HIGHSCORES};
+
+use std::cmp::Reverse;
+use std::io;
+
+pub const RECORDS_FILE: &str = "records.txt";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    //➔ what the record is ranked by: a time in milliseconds, a score...
+    pub value: u64,
+    pub details: Vec<u64>,
+}
+
+fn parse_line(line: &str) -> Option<(&str, Record)> {
+    let (board, rest) = line.split_once(char::is_whitespace)?;
+    let mut numbers = rest.split_whitespace().map(|number| number.parse::<u64>());
+    let value = numbers.next()?.ok()?;
+    let details = numbers.collect::<Result<Vec<_>, _>>().ok()?;
+    Some((board, Record { value, details }))
+}
+
+fn read_records() -> Vec<(String, Record)> {
+    //➔ Without a records file yet, every board is empty
+    let content = read_from_file(RECORDS_FILE).unwrap_or_default();
+    content.lines()
+        .filter_map(parse_line)
+        .map(|(board, record)| (board.to_string(), record))
+        .collect()
+}
+
+fn sort_board(records: &mut [Record], lower_is_better: bool) {
+    if lower_is_better {
+        records.sort_by_key(|record| record.value);
+    } else {
+        records.sort_by_key(|record| Reverse(record.value));
+    }
+}
+
+//➔ The records of `board`, best first
+pub fn load_board(board: &str, lower_is_better: bool) -> Vec<Record> {
+    let mut records = read_records().into_iter()
+        .filter(|(name, _)| name == board)
+        .map(|(_, record)| record)
+        .collect::<Vec<_>>();
+    sort_board(&mut records, lower_is_better);
+    records
+}
+
+//➔ Adds `record` to `board`, returns its rank (0 for the best) if it made it onto the board
+pub fn add_record(board: &str, lower_is_better: bool, record: Record) -> io::Result<Option<usize>> {
+    let mut all = read_records();
+    let mut records = all.iter()
+        .filter(|(name, _)| name == board)
+        .map(|(_, record)| record.clone())
+        .collect::<Vec<_>>();
+    records.push(record.clone());
+    sort_board(&mut records, lower_is_better);
+    records.truncate(NUM_HIGHSCORES);
+    let rank = records.iter().position(|kept| *kept == record);
+    if rank.is_none() {
+        return Ok(None);
+    }
+
+    all.retain(|(name, _)| name != board);
+    all.extend(records.into_iter().map(|record| (board.to_string(), record)));
+    let mut content = String::new();
+    for (name, record) in all.iter() {
+        let mut numbers = vec![record.value];
+        numbers.extend(record.details.iter().copied());
+        let numbers = numbers.iter().map(|number| number.to_string()).collect::<Vec<_>>().join(" ");
+        content.push_str(&format!("{} {}\n", name, numbers));
+    }
+    write_into_file(&content, RECORDS_FILE)?;
+    Ok(rank)
+}
@@ -0,0 +1,146 @@
+//➔ Recording the last game and playing it back.
+//
+// A replay only needs what the engine needs: the seed of the randomizer, the
+// mode and start level, the handling and every action with the engine time it was
+// applied at. Same plain text format as the save file, one entry per line.
+
+use crate::game::GameSetup;
+use crate::handling::Handling;
+use crate::input::TimedAction;
+use crate::mode::GameMode;
+use crate::ruleset::Ruleset;
+use crate::save::{invalid, parse_value};
+use crate::settings::Action;
+use crate::variant::Variant;
+use crate::{read_from_file, write_into_file};
+
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+pub const REPLAY_FILE: &str = "replay.txt";
+const REPLAY_VERSION: u32 = 1;
+
+pub struct Replay {
+    //➔ always has a seed
+    pub setup: GameSetup,
+    pub handling: Handling,
+    pub actions: Vec<TimedAction>,
+}
+
+impl Replay {
+    pub fn save(&self) -> io::Result<()> {
+        write_into_file(&self.text(), REPLAY_FILE)
+    }
+
+    //➔ The replay as the replay file writes it, read back by `parse`
+    pub fn text(&self) -> String {
+        let mut content = String::new();
+        content.push_str(&format!("version {}\n", REPLAY_VERSION));
+        content.push_str(&format!("seed {}\n", self.setup.seed.unwrap_or_default()));
+        content.push_str(&format!("mode {}\n", self.setup.mode.name()));
+        content.push_str(&format!("ruleset {}\n", self.setup.ruleset.name()));
+        content.push_str(&format!("variant {}\n", self.setup.variant.file_name()));
+        content.push_str(&format!("level {}\n", self.setup.start_level));
+        content.push_str(&format!("sprint_lines {}\n", self.setup.sprint_lines));
+        content.push_str(&format!("ultra_seconds {}\n", self.setup.ultra_seconds));
+        content.push_str(&format!("marathon_goal {}\n", self.setup.marathon_goal));
+        content.push_str(&format!("dig {} {}\n", self.setup.dig_lines, self.setup.dig_messiness));
+        content.push_str(&format!("zen {} {}\n", self.setup.zen_gravity, self.setup.zen_undo));
+        content.push_str(&format!("invisible {}\n", self.setup.invisible_fade));
+        content.push_str(&format!("puzzle {} {}\n", self.setup.puzzle_index, self.setup.puzzle_pack));
+        content.push_str(&format!("handling {} {} {}\n",
+            self.handling.das.as_millis(), self.handling.arr.as_millis(), self.handling.sdf));
+        for action in self.actions.iter() {
+            content.push_str(&format!("action {} {} {}\n",
+                action.time.as_millis(), action.action.name(), action.pressed as u32));
+        }
+        content
+    }
+
+    pub fn load() -> io::Result<Replay> {
+        Replay::parse(&read_from_file(REPLAY_FILE)?)
+    }
+
+    pub fn parse(content: &str) -> io::Result<Replay> {
+        let mut replay = Replay {
+            setup: GameSetup {
+                seed: Some(0),
+                ..GameSetup::default()
+            },
+            handling: Handling {
+                das: Duration::ZERO,
+                arr: Duration::ZERO,
+                sdf: 1,
+            },
+            actions: Vec::new(),
+        };
+        for line in content.lines() {
+            let mut parts = line.split_whitespace();
+            let key = match parts.next() {
+                Some(key) => key,
+                None => continue,
+            };
+            match key {
+                "version" => {
+                    let version: u32 = parse_value(key, parts.next())?;
+                    if version > REPLAY_VERSION {
+                        return Err(invalid(format!("replay version {} is newer than this game", version)));
+                    }
+                },
+                "seed" => replay.setup.seed = Some(parse_value(key, parts.next())?),
+                "level" => replay.setup.start_level = parse_value(key, parts.next())?,
+                "ruleset" => {
+                    let name: String = parse_value(key, parts.next())?;
+                    replay.setup.ruleset = Ruleset::from_name(&name).ok_or_else(|| invalid(format!("unknown ruleset `{}`", name)))?;
+                },
+                "variant" => {
+                    let name: String = parse_value(key, parts.next())?;
+                    replay.setup.variant = Variant::from_file_name(&name).ok_or_else(|| invalid(format!("unknown variant `{}`", name)))?;
+                },
+                "sprint_lines" => replay.setup.sprint_lines = parse_value(key, parts.next())?,
+                "ultra_seconds" => replay.setup.ultra_seconds = parse_value(key, parts.next())?,
+                "marathon_goal" => replay.setup.marathon_goal = parse_value(key, parts.next())?,
+                "dig" => {
+                    replay.setup.dig_lines = parse_value(key, parts.next())?;
+                    replay.setup.dig_messiness = parse_value(key, parts.next())?;
+                },
+                "zen" => {
+                    replay.setup.zen_gravity = parse_value(key, parts.next())?;
+                    replay.setup.zen_undo = parse_value(key, parts.next())?;
+                },
+                "invisible" => replay.setup.invisible_fade = parse_value(key, parts.next())?,
+                "puzzle" => {
+                    replay.setup.puzzle_index = parse_value(key, parts.next())?;
+                    replay.setup.puzzle_pack = parts.collect::<Vec<_>>().join(" ");
+                },
+                "mode" => {
+                    let name: String = parse_value(key, parts.next())?;
+                    replay.setup.mode = GameMode::from_name(&name).ok_or_else(|| invalid(format!("unknown mode `{}`", name)))?;
+                },
+                "handling" => {
+                    replay.handling.das = Duration::from_millis(parse_value(key, parts.next())?);
+                    replay.handling.arr = Duration::from_millis(parse_value(key, parts.next())?);
+                    replay.handling.sdf = parse_value::<u32>(key, parts.next())?.max(1);
+                },
+                "action" => {
+                    let time = Duration::from_millis(parse_value(key, parts.next())?);
+                    let name: String = parse_value(key, parts.next())?;
+                    let action = Action::from_name(&name).ok_or_else(|| invalid(format!("unknown action `{}`", name)))?;
+                    let pressed = parse_value::<u32>(key, parts.next())? != 0;
+                    replay.actions.push(TimedAction { time, action, pressed });
+                },
+                _ => {},
+            }
+        }
+        let (min_level, _) = replay.setup.ruleset.start_levels();
+        if replay.setup.start_level < min_level {
+            return Err(invalid(format!("level must be at least {}", min_level)));
+        }
+        Ok(replay)
+    }
+
+    pub fn exists() -> bool {
+        Path::new(REPLAY_FILE).exists()
+    }
+}
@@ -0,0 +1,345 @@
+//➔ The rules a game is played by.
+//
+// The standard rules are the ones the game always had: its own gravity table,
+// a level up every 10 lines, hold, ghost, the whole preview and kicks off the
+// walls and pieces when rotating. The NES rules follow NES Tetris for players
+// training for classic tournaments: the NES gravity in frames per row, its
+// scoring and randomizer, a single preview, no hold, ghost or kicks, the NES
+// rule for the first level up when starting past level 0, and the colors of
+// the blocks changing with the level. ARS is the rotation system of the Grand
+// Master games, which only comes with the master mode: its gravity going up to
+// 20G, a randomizer that avoids the last pieces dealt, a single preview, and
+// pieces of its own. These turn in a box of three cells (four for the I), come
+// in flat side up at the bottom of it, and kick a single cell to the right or
+// left when a rotation does not fit, except for the I, and for the L, J and T
+// when the first cell in their way is in the middle column of the box.
+
+use crate::randomizer::Randomizer;
+use crate::Tetrimino;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Ruleset {
+    Standard,
+    Nes,
+    Ars,
+}
+
+//➔ Lines to clear for every level up
+const LINES_PER_LEVEL: u32 = 10;
+
+//➔ Milliseconds between two drops at each standard level, from level 0
+const STANDARD_TIMES: [u32; 20] = [1000, 850, 750, 650, 600, 550, 500, 400, 350, 300,
+    250, 200, 170, 140, 110, 90, 70, 50, 35, 20];
+
+//➔ Frames between two drops at each NES level, from level 0. From level 29 on a piece drops every frame
+const NES_FRAMES: [u32; 30] = [48, 43, 38, 33, 28, 23, 18, 13, 8, 6, 5, 5, 5, 4, 4, 4, 3, 3, 3,
+    2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 1];
+//➔ The NES draws about 60.1 frames per second
+const NES_FRAME_MICROS: u32 = 16_639;
+
+//➔ ARS gravity from the level it starts at, in 1/256 rows per frame at 60 frames per second
+const ARS_GRAVITY: [(u32, u32); 30] = [(0, 4), (30, 6), (35, 8), (40, 10), (50, 12), (60, 16), (70, 32),
+    (80, 48), (90, 64), (100, 80), (120, 96), (140, 112), (160, 128), (170, 144), (200, 4), (220, 32),
+    (230, 64), (233, 96), (236, 128), (239, 160), (243, 192), (247, 224), (251, 256), (300, 512),
+    (330, 768), (360, 1024), (400, 1280), (420, 1024), (450, 768), (500, TWENTY_G)];
+//➔ 20 rows per frame, as fast as it gets: a piece drops to the floor as soon as it comes in
+pub const TWENTY_G: u32 = 5120;
+
+//➔ Sideways moves tried when a rotation does not fit where the piece is
+const STANDARD_KICKS: [isize; 7] = [0, -1, 1, -2, 2, -3, 3];
+const ARS_KICKS: [isize; 3] = [0, 1, -1];
+const NO_KICKS: [isize; 1] = [0];
+
+//➔ The ARS states of every kind, clockwise from the one it comes in with, in the box it turns in.
+//   The S, Z and I only have two states, given twice
+const ARS_STATES: [[[&str; 4]; 4]; 7] = [
+    [["....", "####", "....", "...."], ["..#.", "..#.", "..#.", "..#."],
+     ["....", "####", "....", "...."], ["..#.", "..#.", "..#.", "..#."]],
+    [["....", ".##.", ".##.", "...."], ["....", ".##.", ".##.", "...."],
+     ["....", ".##.", ".##.", "...."], ["....", ".##.", ".##.", "...."]],
+    [["....", "###.", ".#..", "...."], [".#..", "##..", ".#..", "...."],
+     ["....", ".#..", "###.", "...."], [".#..", ".##.", ".#..", "...."]],
+    [["....", ".##.", "##..", "...."], ["#...", "##..", ".#..", "...."],
+     ["....", ".##.", "##..", "...."], ["#...", "##..", ".#..", "...."]],
+    [["....", "##..", ".##.", "...."], ["..#.", ".##.", ".#..", "...."],
+     ["....", "##..", ".##.", "...."], ["..#.", ".##.", ".#..", "...."]],
+    [["....", "###.", "..#.", "...."], [".#..", ".#..", "##..", "...."],
+     ["....", "#...", "###.", "...."], [".##.", ".#..", ".#..", "...."]],
+    [["....", "###.", "#...", "...."], ["##..", ".#..", ".#..", "...."],
+     ["....", "..#.", "###.", "...."], [".#..", ".#..", ".##.", "...."]],
+];
+//➔ Column of the left edge of the box of a new ARS piece
+const ARS_SPAWN_X: isize = 3;
+
+type Rgb = (u8, u8, u8);
+
+//➔ The two colors of every NES level, the palettes start over every 10 levels
+const NES_PALETTES: [(Rgb, Rgb); 10] = [
+    ((0, 88, 248), (60, 188, 252)),
+    ((0, 168, 0), (184, 248, 24)),
+    ((216, 0, 204), (248, 120, 248)),
+    ((0, 88, 248), (88, 216, 84)),
+    ((228, 0, 88), (88, 248, 152)),
+    ((88, 248, 152), (104, 136, 252)),
+    ((248, 56, 0), (124, 124, 124)),
+    ((104, 68, 252), (168, 0, 32)),
+    ((0, 88, 248), (248, 56, 0)),
+    ((248, 56, 0), (252, 160, 68)),
+];
+const WHITE: Rgb = (248, 248, 248);
+const GARBAGE_GRAY: Rgb = (124, 124, 124);
+
+//➔ Border and fill color of a block
+pub type BlockColors = (Rgb, Rgb);
+
+//➔ ARS gravity at `level`, in 1/256 rows per frame
+pub fn ars_gravity(level: u32) -> u32 {
+    ARS_GRAVITY.iter().rev().find(|&&(from, _)| level >= from).map_or(ARS_GRAVITY[0].1, |&(_, gravity)| gravity)
+}
+
+//➔ Column in the box of the first cell in the way of `piece` turned by `step` where it is,
+//   row by row from the top left, the walls and floor being in the way
+fn first_blocked_column(piece: &Tetrimino, game_map: &[Vec<u8>], step: isize) -> Option<usize> {
+    let state = &piece.states[piece.rotated_state(step)];
+    (0..4).flat_map(|row| (0..4).map(move |col| (row, col))).find(|&(row, col)| {
+        let (y, x) = (piece.y + row, piece.x + col as isize);
+        state[row][col] != 0 && (x < 0 || y >= game_map.len() || x as usize >= game_map[y].len()
+            || game_map[y][x as usize] != 0)
+    }).map(|(_, col)| col)
+}
+
+impl Ruleset {
+    //➔ The rulesets that can be picked, ARS only comes with the master mode
+    pub const ALL: [Ruleset; 2] = [Ruleset::Standard, Ruleset::Nes];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Ruleset::Standard => "STANDARD",
+            Ruleset::Nes => "NES",
+            Ruleset::Ars => "ARS",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Ruleset> {
+        Ruleset::ALL.iter().copied().find(|ruleset| ruleset.name() == name)
+    }
+
+    //➔ The ruleset `step` entries further in `ALL`, wrapping around at both ends
+    pub fn cycle(self, step: i32) -> Ruleset {
+        let len = Ruleset::ALL.len() as i32;
+        let index = Ruleset::ALL.iter().position(|&ruleset| ruleset == self).unwrap_or(0) as i32;
+        Ruleset::ALL[(index + step).rem_euclid(len) as usize]
+    }
+
+    //➔ Lowest and highest level a game can be started from
+    pub fn start_levels(&self) -> (u32, u32) {
+        match self {
+            Ruleset::Standard => (1, 15),
+            Ruleset::Nes => (0, 19),
+            Ruleset::Ars => (0, 0),
+        }
+    }
+
+    //➔ Milliseconds between two drops at `level`, levels past the end of a table keep its last speed
+    pub fn drop_time(&self, level: u32) -> u32 {
+        match self {
+            Ruleset::Standard => STANDARD_TIMES[(level as usize).min(STANDARD_TIMES.len() - 1)],
+            Ruleset::Nes => NES_FRAMES[(level as usize).min(NES_FRAMES.len() - 1)] * NES_FRAME_MICROS / 1000,
+            Ruleset::Ars => (256 * 1000 / 60 / ars_gravity(level)).max(1),
+        }
+    }
+
+    //➔ Whether the line number `lines` brings a new level, in a game started at `start_level`.
+    //   On the NES the first level up comes after `start_level * 10 + 10` lines, or later
+    //   when starting from level 10 on, and then every 10 lines. ARS levels are counted by the master mode
+    pub fn levels_up(&self, start_level: u32, lines: u32) -> bool {
+        match self {
+            Ruleset::Standard => lines.is_multiple_of(LINES_PER_LEVEL),
+            Ruleset::Nes => {
+                let first = (start_level * 10 + 10).min(100.max((start_level * 10).saturating_sub(50)));
+                lines >= first && (lines - first).is_multiple_of(LINES_PER_LEVEL)
+            },
+            Ruleset::Ars => false,
+        }
+    }
+
+    //➔ Kind of the next piece
+    pub fn next_kind(&self, randomizer: &mut Randomizer) -> u8 {
+        match self {
+            Ruleset::Standard => randomizer.next_kind(),
+            Ruleset::Nes => randomizer.next_kind_nes(),
+            Ruleset::Ars => randomizer.next_kind_tgm(),
+        }
+    }
+
+    pub fn has_hold(&self) -> bool {
+        *self != Ruleset::Nes
+    }
+
+    pub fn has_ghost(&self) -> bool {
+        *self != Ruleset::Nes
+    }
+
+    //➔ A new piece of `kind`, where it comes in on a board of the normal width
+    pub fn new_piece(&self, kind: u8) -> Tetrimino {
+        let mut piece = Tetrimino::from_kind(kind);
+        if *self == Ruleset::Ars {
+            let color = kind + 1;
+            piece.states = ARS_STATES[kind as usize].iter().map(|state| {
+                state.iter().map(|row| row.bytes().map(|cell| if cell == b'#' { color } else { 0 }).collect()).collect()
+            }).collect();
+            piece.x = ARS_SPAWN_X;
+        }
+        piece
+    }
+
+    //➔ Sideways moves turning `piece` by `step` quarter turns may make to fit, in the order they are tried.
+    //   In ARS the I never kicks, and neither do the L, J and T when the first cell in their way,
+    //   row by row from the top left of the box, is in the middle column
+    pub fn kicks(&self, piece: &Tetrimino, game_map: &[Vec<u8>], step: isize) -> &'static [isize] {
+        match self {
+            Ruleset::Standard => &STANDARD_KICKS,
+            Ruleset::Nes => &NO_KICKS,
+            Ruleset::Ars => match piece.kind() {
+                0 => &NO_KICKS,
+                2 | 5 | 6 if first_blocked_column(piece, game_map, step) == Some(1) => &NO_KICKS,
+                _ => &ARS_KICKS,
+            },
+        }
+    }
+
+    //➔ Upcoming pieces shown when the player asked for `wanted` of them, the NES and the Grand
+    //   Master games only show the next one
+    pub fn preview(&self, wanted: u32) -> u32 {
+        match self {
+            Ruleset::Standard => wanted,
+            Ruleset::Nes | Ruleset::Ars => wanted.min(1),
+        }
+    }
+
+    //➔ Colors of every block value at `level`, none when the blocks keep their usual colors.
+    //   As on the NES, the I, O and T are white inside a border of the first color of the level,
+    //   the J and Z take the first color and the L and S the second one
+    pub fn palette(&self, level: u32) -> Option<[BlockColors; 8]> {
+        if *self != Ruleset::Nes {
+            return None;
+        }
+        let (first, second) = NES_PALETTES[level as usize % NES_PALETTES.len()];
+        Some([(first, WHITE), (first, WHITE), (first, WHITE), (second, second), (first, first),
+            (first, first), (second, second), (GARBAGE_GRAY, GARBAGE_GRAY)])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty_board() -> Vec<Vec<u8>> {
+        vec![vec![0; 10]; 16]
+    }
+
+    fn blocks(piece: &Tetrimino) -> Vec<(usize, isize)> {
+        let state = &piece.states[piece.current_state];
+        (0..4).flat_map(|row| (0..4).map(move |col| (row, col)))
+            .filter(|&(row, col)| state[row][col] != 0)
+            .map(|(row, col)| (piece.y + row, piece.x + col as isize))
+            .collect()
+    }
+
+    #[test]
+    fn ars_pieces_have_four_blocks_and_keep_their_kind() {
+        for kind in 0..7 {
+            let piece = Ruleset::Ars.new_piece(kind);
+            assert_eq!(piece.kind(), kind);
+            assert_eq!(piece.states.len(), 4);
+            for state in piece.states.iter() {
+                assert_eq!(state.iter().flatten().filter(|&&block| block != 0).count(), 4);
+            }
+        }
+    }
+
+    #[test]
+    fn ars_t_l_and_j_turn_through_four_states() {
+        for kind in [2, 5, 6] {
+            let mut piece = Ruleset::Ars.new_piece(kind);
+            piece.y = 4;
+            let mut seen = Vec::new();
+            for _ in 0..4 {
+                seen.push(blocks(&piece));
+                let board = empty_board();
+                let kicks = Ruleset::Ars.kicks(&piece, &board, 1);
+                piece.rotate_by(&board, 1, kicks);
+            }
+            assert_eq!(blocks(&piece), seen[0]);
+            seen.dedup();
+            assert_eq!(seen.len(), 4, "kind {}", kind);
+        }
+    }
+
+    #[test]
+    fn ars_pieces_come_in_flat_side_up() {
+        let piece = Ruleset::Ars.new_piece(2);
+        assert_eq!(blocks(&piece), vec![(1, 3), (1, 4), (1, 5), (2, 4)]);
+    }
+
+    #[test]
+    fn ars_kicks_off_a_block_outside_the_middle_column() {
+        let mut board = empty_board();
+        let mut piece = Ruleset::Ars.new_piece(2);
+        piece.y = 5;
+        //➔ in the way of the left column of the turned T, the piece kicks to the right
+        board[6][3] = 8;
+        let kicks = Ruleset::Ars.kicks(&piece, &board, 1);
+        assert_eq!(kicks, &ARS_KICKS);
+        piece.rotate_by(&board, 1, kicks);
+        assert_eq!((piece.current_state, piece.x), (1, 4));
+        assert!(piece.test_current_position(&board));
+    }
+
+    #[test]
+    fn ars_does_not_kick_off_a_block_in_the_middle_column() {
+        let mut board = empty_board();
+        let mut piece = Ruleset::Ars.new_piece(2);
+        piece.y = 5;
+        board[5][4] = 8;
+        let kicks = Ruleset::Ars.kicks(&piece, &board, 1);
+        assert_eq!(kicks, &NO_KICKS);
+        piece.rotate_by(&board, 1, kicks);
+        assert_eq!((piece.current_state, piece.x), (0, 3));
+    }
+
+    #[test]
+    fn ars_i_never_kicks() {
+        let piece = Ruleset::Ars.new_piece(0);
+        assert_eq!(Ruleset::Ars.kicks(&piece, &empty_board(), 1), &NO_KICKS);
+    }
+
+    #[test]
+    fn ars_shows_a_single_preview() {
+        assert_eq!(Ruleset::Ars.preview(5), 1);
+        assert_eq!(Ruleset::Standard.preview(5), 5);
+    }
+
+    #[test]
+    fn nes_gravity_follows_the_frames_of_each_level() {
+        let frames = [(0, 48), (1, 43), (5, 23), (8, 8), (9, 6), (10, 5), (12, 5), (13, 4), (15, 4), (16, 3),
+            (18, 3), (19, 2), (28, 2), (29, 1), (40, 1)];
+        for (level, frames) in frames {
+            assert_eq!(Ruleset::Nes.drop_time(level), frames * NES_FRAME_MICROS / 1000, "level {}", level);
+        }
+        assert_eq!(Ruleset::Nes.drop_time(0), 798);
+    }
+
+    #[test]
+    fn nes_first_level_up_follows_the_transition_rule() {
+        //➔ the start level and the lines of its first level up
+        let transitions = [(0, 10), (5, 60), (9, 100), (10, 100), (15, 100), (16, 110), (18, 130), (19, 140)];
+        for (start_level, first) in transitions {
+            let level_ups = (1..=first).filter(|&lines| Ruleset::Nes.levels_up(start_level, lines)).collect::<Vec<_>>();
+            assert_eq!(level_ups, vec![first], "start level {}", start_level);
+            assert!(Ruleset::Nes.levels_up(start_level, first + 10));
+            assert!(!Ruleset::Nes.levels_up(start_level, first + 5));
+        }
+    }
+
+}
@@ -0,0 +1,266 @@
+//➔ Saving and resuming an in-progress game.
+//
+// The save file is plain text in the same spirit as the highscore file: one
+// `key value...` entry per line, with every row of the game map on its own
+// `row` line. Unknown keys are ignored so older saves keep loading.
+
+use crate::clock::GameClock;
+use crate::game::GameSetup;
+use crate::mode::GameMode;
+use crate::{line_to_slice, read_from_file, slice_to_string, write_into_file, Randomizer, Tetrimino, Tetris, GARBAGE};
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+pub const SAVE_FILE: &str = "savegame.txt";
+const SAVE_VERSION: u32 = 1;
+
+pub fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+pub fn parse_value<T: FromStr>(key: &str, value: Option<&str>) -> io::Result<T> {
+    let value = value.ok_or_else(|| invalid(format!("missing value for `{}`", key)))?;
+    value.parse::<T>().map_err(|_| invalid(format!("bad value `{}` for `{}`", value, key)))
+}
+
+//➔ `timer` is the engine time of the last drop, as used by the gravity in the game loop.
+//   Only marathons are saved, `setup` holds their start level and goal
+pub fn save_game(setup: &GameSetup, tetris: &Tetris, clock: &GameClock, timer: Duration) -> io::Result<()> {
+    write_into_file(&game_text(setup, tetris, clock, timer), SAVE_FILE)
+}
+
+//➔ The save file of a game, read back by `parse_game`
+fn game_text(setup: &GameSetup, tetris: &Tetris, clock: &GameClock, timer: Duration) -> String {
+    let mut content = String::new();
+    content.push_str(&format!("version {}\n", SAVE_VERSION));
+    content.push_str(&format!("start_level {}\n", setup.start_level));
+    content.push_str(&format!("goal {}\n", setup.marathon_goal));
+    content.push_str(&format!("score {}\n", tetris.score));
+    content.push_str(&format!("level {}\n", tetris.current_level));
+    content.push_str(&format!("lines {}\n", tetris.num_lines));
+    content.push_str(&format!("seed {}\n", tetris.seed));
+    content.push_str(&format!("randomizer {} {}\n", tetris.randomizer.state(), tetris.randomizer.prev()));
+    content.push_str(&format!("pieces {}\n", slice_to_string(&tetris.piece_counts)));
+    content.push_str(&format!("clock {}\n", clock.now().as_millis()));
+    content.push_str(&format!("timer {}\n", clock.now().saturating_sub(timer).as_millis()));
+    let next_kinds = tetris.next_kinds.iter().map(|&kind| kind as u32).collect::<Vec<_>>();
+    content.push_str(&format!("next {}\n", slice_to_string(&next_kinds)));
+    if let Some(kind) = tetris.held_kind {
+        content.push_str(&format!("hold {} {}\n", kind, tetris.hold_used as u32));
+    }
+    if let Some(ref piece) = tetris.current_piece {
+        content.push_str(&format!("piece {} {} {} {}\n", piece.kind(), piece.current_state, piece.x, piece.y));
+    }
+    for row in tetris.game_map.iter() {
+        let row = row.iter().map(|block| block.to_string()).collect::<Vec<_>>().join(" ");
+        content.push_str(&format!("row {}\n", row));
+    }
+    content
+}
+
+//➔ Returns the game together with a clock and gravity timer picking up where they stopped.
+//   The start level and goal of the saved marathon go into `setup`, saves from before
+//   they were kept are endless marathons from level 1
+pub fn load_game(setup: &mut GameSetup) -> io::Result<(Tetris, GameClock, Duration)> {
+    parse_game(&read_from_file(SAVE_FILE)?, setup)
+}
+
+fn parse_game(content: &str, setup: &mut GameSetup) -> io::Result<(Tetris, GameClock, Duration)> {
+    let mut tetris = Tetris::new();
+    let mut start_level = 1;
+    let mut goal = 0;
+    let mut clock_millis: u64 = 0;
+    let mut timer_millis: u64 = 0;
+    let mut game_map = Vec::new();
+
+    for line in content.lines() {
+        let mut parts = line.split_whitespace();
+        let key = match parts.next() {
+            Some(key) => key,
+            None => continue,
+        };
+        match key {
+            "version" => {
+                let version: u32 = parse_value(key, parts.next())?;
+                if version > SAVE_VERSION {
+                    return Err(invalid(format!("save file version {} is newer than this game", version)));
+                }
+            },
+            "start_level" => start_level = parse_value(key, parts.next())?,
+            "goal" => goal = parse_value(key, parts.next())?,
+            "score" => tetris.score = parse_value(key, parts.next())?,
+            "level" => tetris.current_level = parse_value(key, parts.next())?,
+            "lines" => tetris.num_lines = parse_value(key, parts.next())?,
+            "seed" => tetris.seed = parse_value(key, parts.next())?,
+            "clock" => clock_millis = parse_value(key, parts.next())?,
+            "timer" => timer_millis = parse_value(key, parts.next())?,
+            "randomizer" => {
+                let state = parse_value(key, parts.next())?;
+                let prev = parse_value(key, parts.next())?;
+                tetris.randomizer = Randomizer::from_state(state, prev);
+            },
+            "piece" => {
+                let kind: u8 = parse_value(key, parts.next())?;
+                if kind >= 7 {
+                    return Err(invalid(format!("unknown piece kind {}", kind)));
+                }
+                let mut piece = Tetrimino::from_kind(kind);
+                piece.current_state = parse_value(key, parts.next())?;
+                piece.x = parse_value(key, parts.next())?;
+                piece.y = parse_value(key, parts.next())?;
+                if piece.current_state >= piece.states.len() {
+                    return Err(invalid(format!("piece state {} out of range", piece.current_state)));
+                }
+                tetris.current_piece = Some(piece);
+            },
+            "hold" => {
+                let kind: u8 = parse_value(key, parts.next())?;
+                if kind >= 7 {
+                    return Err(invalid(format!("unknown piece kind {}", kind)));
+                }
+                let used: u32 = parse_value(key, parts.next())?;
+                tetris.held_kind = Some(kind);
+                tetris.hold_used = used != 0;
+            },
+            "next" => {
+                let next_kinds = line_to_slice(line.trim_start_matches("next"));
+                if next_kinds.iter().any(|&kind| kind >= 7) {
+                    return Err(invalid(format!("unknown piece kind in `{}`", line)));
+                }
+                tetris.next_kinds = next_kinds.into_iter().map(|kind| kind as u8).collect();
+            },
+            "pieces" => {
+                let counts = line_to_slice(line.trim_start_matches("pieces"));
+                if counts.len() != tetris.piece_counts.len() {
+                    return Err(invalid(format!("malformed piece counts `{}`", line)));
+                }
+                tetris.piece_counts.copy_from_slice(&counts);
+            },
+            "row" => {
+                let row = line_to_slice(line.trim_start_matches("row").trim());
+                if row.len() != 10 || row.iter().any(|&block| block > GARBAGE as u32) {
+                    return Err(invalid(format!("malformed row `{}`", line)));
+                }
+                game_map.push(row.into_iter().map(|block| block as u8).collect::<Vec<u8>>());
+            },
+            _ => {},
+        }
+    }
+    if game_map.len() != tetris.game_map.len() {
+        return Err(invalid(format!("expected {} rows, found {}", tetris.game_map.len(), game_map.len())));
+    }
+    tetris.game_map = game_map;
+    //➔ a piece outside of the board or inside its blocks could not have been saved
+    if let Some(ref piece) = tetris.current_piece {
+        if piece.y >= tetris.game_map.len() || !piece.test_current_position(&tetris.game_map) {
+            return Err(invalid(format!("piece at {} {} does not fit in the board", piece.x, piece.y)));
+        }
+    }
+    if tetris.current_level == 0 {
+        return Err(invalid("level must be at least 1".to_string()));
+    }
+    setup.mode = GameMode::Marathon;
+    setup.start_level = start_level.max(1);
+    setup.marathon_goal = goal;
+    setup.seed = None;
+    let clock = GameClock::starting_at(Duration::from_millis(clock_millis));
+    let timer = clock.now().saturating_sub(Duration::from_millis(timer_millis));
+    Ok((tetris, clock, timer))
+}
+
+pub fn has_save() -> bool {
+    Path::new(SAVE_FILE).exists()
+}
+
+pub fn remove_save() {
+    //➔ A missing save file is fine, there is simply nothing to continue
+    let _ = fs::remove_file(SAVE_FILE);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //➔ A marathon with a piece falling, a held piece and some blocks at the bottom
+    fn saved_game() -> (Tetris, String) {
+        let mut tetris = Tetris::from_seed(42);
+        tetris.score = 1200;
+        tetris.current_level = 3;
+        tetris.num_lines = 21;
+        tetris.piece_counts = [1, 2, 3, 4, 5, 6, 7];
+        tetris.held_kind = Some(4);
+        tetris.hold_used = true;
+        let bottom = tetris.game_map.len() - 1;
+        tetris.game_map[bottom] = vec![GARBAGE, GARBAGE, 0, 1, 2, 3, 4, 5, 6, 7];
+        let mut piece = Tetrimino::from_kind(2);
+        piece.x = 3;
+        piece.y = 0;
+        tetris.current_piece = Some(piece);
+        let setup = GameSetup { start_level: 2, marathon_goal: 150, ..GameSetup::default() };
+        let clock = GameClock::starting_at(Duration::from_secs(90));
+        let text = game_text(&setup, &tetris, &clock, Duration::from_secs(89));
+        (tetris, text)
+    }
+
+    #[test]
+    fn saved_game_loads_back() {
+        let (saved, text) = saved_game();
+        let mut setup = GameSetup::default();
+        let (tetris, clock, _) = parse_game(&text, &mut setup).expect("the saved game should load");
+        assert_eq!(setup.start_level, 2);
+        assert_eq!(setup.marathon_goal, 150);
+        assert_eq!(tetris.score, saved.score);
+        assert_eq!(tetris.current_level, saved.current_level);
+        assert_eq!(tetris.num_lines, saved.num_lines);
+        assert_eq!(tetris.seed, saved.seed);
+        assert_eq!(tetris.piece_counts, saved.piece_counts);
+        assert_eq!(tetris.next_kinds, saved.next_kinds);
+        assert_eq!(tetris.held_kind, Some(4));
+        assert!(tetris.hold_used);
+        assert_eq!(tetris.game_map, saved.game_map);
+        let piece = tetris.current_piece.expect("the falling piece should be kept");
+        assert_eq!((piece.kind(), piece.x, piece.y), (2, 3, 0));
+        assert!(clock.now() >= Duration::from_secs(90));
+    }
+
+    fn load_with(text: &str, from: &str, to: &str) -> io::Result<(Tetris, GameClock, Duration)> {
+        parse_game(&text.replace(from, to), &mut GameSetup::default())
+    }
+
+    #[test]
+    fn piece_outside_of_the_board_is_refused() {
+        let (_, text) = saved_game();
+        assert!(load_with(&text, "piece 2 0 3 0", "piece 2 0 40 99").is_err());
+        assert!(load_with(&text, "piece 2 0 3 0", "piece 2 0 -5 0").is_err());
+    }
+
+    #[test]
+    fn piece_inside_blocks_is_refused() {
+        let (_, text) = saved_game();
+        let bottom = Tetris::from_seed(0).game_map.len() - 2;
+        assert!(load_with(&text, "piece 2 0 3 0", &format!("piece 2 0 0 {}", bottom)).is_err());
+    }
+
+    #[test]
+    fn bad_entries_are_refused() {
+        let (_, text) = saved_game();
+        assert!(load_with(&text, "piece 2 0", "piece 9 0").is_err());
+        assert!(load_with(&text, "hold 4", "hold 7").is_err());
+        assert!(load_with(&text, "score 1200", "score lots").is_err());
+        assert!(load_with(&text, "level 3", "level 0").is_err());
+        assert!(load_with(&text, "pieces 1 2 3", "pieces 1 2").is_err());
+        assert!(load_with(&text, "row 8 8 0 1", "row 263 8 0 1").is_err());
+        assert!(load_with(&text, "version 1", "version 99").is_err());
+    }
+
+    #[test]
+    fn missing_rows_are_refused() {
+        let (_, text) = saved_game();
+        let without_last_row = text.lines().filter(|line| !line.starts_with("row 8")).collect::<Vec<_>>().join("\n");
+        assert!(parse_game(&without_last_row, &mut GameSetup::default()).is_err());
+    }
+}
@@ -0,0 +1,113 @@
+//➔ How clearing lines and dropping pieces is scored.
+//
+// Classic is the scoring the game always had: a point per level for every piece
+// placed and for every line cleared. Guideline follows the modern games: points
+// for singles up to tetrises and for T-spins scaled by the level, a bonus for
+// tetrises and T-spin clears back to back, combo points for clearing lines with
+// consecutive pieces, and points for every cell a piece is soft or hard dropped. NES is the scoring of NES Tetris:
+// points for each clear scaled by the level plus one, and for soft dropping.
+// Master is the scoring of the Grand Master games, which only pays for clears:
+// the level and the cells the piece was dropped by, multiplied by the lines,
+// the combo and four times over when the clear emptied the board.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scoring {
+    Classic,
+    Guideline,
+    Nes,
+    Master,
+}
+
+//➔ Points of nothing, a single, double, triple and tetris at level 1
+const LINE_POINTS: [u32; 5] = [0, 100, 300, 500, 800];
+//➔ Points of a T-spin clearing no line, a single, double and triple at level 1
+const T_SPIN_POINTS: [u32; 4] = [400, 800, 1200, 1600];
+//➔ Points per clear in a row at level 1
+const COMBO_POINTS: u32 = 50;
+const SOFT_DROP_POINTS: u32 = 1;
+const HARD_DROP_POINTS: u32 = 2;
+//➔ Points of nothing, a single, double, triple and tetris at NES level 0
+const NES_LINE_POINTS: [u32; 5] = [0, 40, 100, 300, 1200];
+
+//➔ Guideline points for clearing `lines` lines at once, with a T that turned into place if `t_spin`.
+//   `back_to_back` is set when this clear and the one before are both tetrises or T-spins,
+//   `combo` counts the clears in a row before this one
+pub fn line_clear_points(lines: usize, level: u32, t_spin: bool, back_to_back: bool, combo: u32) -> u32 {
+    let points = if t_spin { T_SPIN_POINTS[lines.min(3)] } else { LINE_POINTS[lines.min(4)] };
+    let mut points = points * level;
+    if back_to_back {
+        points = points * 3 / 2;
+    }
+    points + COMBO_POINTS * combo * level
+}
+
+//➔ NES points for clearing `lines` lines at once, NES levels start from 0
+pub fn nes_line_clear_points(lines: usize, level: u32) -> u32 {
+    NES_LINE_POINTS[lines.min(4)] * (level + 1)
+}
+
+//➔ Master points for clearing `lines` lines at once at `level`, with the piece dropped by `dropped` cells.
+//   `combo` starts at 1 and grows with every clear in a row, `bravo` is set when the board is empty
+pub fn master_line_clear_points(lines: usize, level: u32, dropped: u32, combo: u32, bravo: bool) -> u32 {
+    let lines = lines as u32;
+    let points = ((level + lines).div_ceil(4) + dropped) * lines * combo;
+    if bravo {
+        points * 4
+    } else {
+        points
+    }
+}
+
+//➔ Points for dropping a piece by `cells` cells, the NES has no hard drop to score.
+//   Master drops only score along with a clear
+pub fn drop_points(scoring: Scoring, cells: usize, hard: bool) -> u32 {
+    let points = match (scoring, hard) {
+        (Scoring::Classic, _) | (Scoring::Nes, true) | (Scoring::Master, _) => 0,
+        (Scoring::Guideline, true) => HARD_DROP_POINTS,
+        (Scoring::Guideline, false) | (Scoring::Nes, false) => SOFT_DROP_POINTS,
+    };
+    cells as u32 * points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guideline_clears_are_scaled_by_the_level() {
+        let cases = [(0, 1, 0), (1, 1, 100), (2, 1, 300), (3, 1, 500), (4, 1, 800), (1, 3, 300), (4, 5, 4000)];
+        for (lines, level, points) in cases {
+            assert_eq!(line_clear_points(lines, level, false, false, 0), points, "{} lines at level {}", lines, level);
+        }
+    }
+
+    #[test]
+    fn guideline_t_spins_have_their_own_points() {
+        let cases = [(0, 1, 400), (1, 1, 800), (2, 1, 1200), (3, 1, 1600), (2, 4, 4800)];
+        for (lines, level, points) in cases {
+            assert_eq!(line_clear_points(lines, level, true, false, 0), points, "T-spin of {} lines at level {}", lines, level);
+        }
+    }
+
+    #[test]
+    fn guideline_back_to_back_adds_half() {
+        assert_eq!(line_clear_points(4, 1, false, true, 0), 1200);
+        assert_eq!(line_clear_points(2, 2, true, true, 0), 3600);
+    }
+
+    #[test]
+    fn guideline_combo_adds_points_per_clear_in_a_row() {
+        assert_eq!(line_clear_points(1, 1, false, false, 3), 250);
+        assert_eq!(line_clear_points(1, 2, false, false, 3), 500);
+    }
+
+    #[test]
+    fn nes_clears_are_scaled_by_the_level_plus_one() {
+        let cases = [(0, 5, 0), (1, 0, 40), (2, 0, 100), (3, 0, 300), (4, 0, 1200), (1, 18, 760), (2, 9, 1000),
+            (3, 19, 6000), (4, 9, 12000), (4, 19, 24000)];
+        for (lines, level, points) in cases {
+            assert_eq!(nes_line_clear_points(lines, level), points, "{} lines at level {}", lines, level);
+        }
+    }
+
+}
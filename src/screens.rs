@@ -0,0 +1,676 @@
+//➔ Title screen, leaderboards and the other screens shown outside of a game.
+
+use crate::daily;
+use crate::font::{draw_text_centered, text_height};
+use crate::game::{restart_seed, GameEnd, GameSetup};
+use crate::gamepad::{self, Gamepads};
+use crate::graphics::Graphics;
+use crate::hud::format_time;
+use crate::menu::{Menu, MenuInput};
+use crate::mode;
+use crate::netplay::{Role, DEFAULT_PORT};
+use crate::records;
+use crate::replay::Replay;
+use crate::settings::{self, Action, Settings, SETTINGS_FILE};
+use crate::{save, Tetris, TETRIS_HEIGHT};
+
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::pixels::Color;
+
+use std::thread;
+use std::time::Duration;
+
+const TEXT_SCALE: u32 = 3;
+const TITLE_SCALE: u32 = 5;
+
+//➔ Window sizes offered in the settings menu, any other size can be set in the settings file
+const WINDOW_SIZES: [(u32, u32); 5] = [(1024, 640), (1280, 720), (1360, 768), (1600, 900), (1920, 1080)];
+
+pub enum TitleAction {
+    Continue,
+    Play,
+    //➔ play the daily challenge of today
+    Daily,
+    //➔ play a local versus game
+    Versus,
+    //➔ host or join a versus game over the network, see `netplay`
+    Network(Role),
+    WatchReplay,
+    Exit,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum TitleChoice {
+    Continue,
+    Play,
+    Daily,
+    DailyResults,
+    Versus,
+    HostVersus,
+    JoinVersus,
+    Mode,
+    Ruleset,
+    Variant,
+    //➔ a setting of the selected mode, by its index
+    ModeOption(usize),
+    StartLevel,
+    Settings,
+    Leaderboards,
+    Replay,
+    Quit,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum SettingsChoice {
+    Das,
+    Arr,
+    Sdf,
+    Ghost,
+    Preview,
+    WindowSize,
+    Fullscreen,
+    Volume,
+    Theme,
+    Deadzone,
+    RestartSeed,
+    Controls,
+    Back,
+}
+
+const SETTINGS_CHOICES: [SettingsChoice; 13] = [SettingsChoice::Das, SettingsChoice::Arr, SettingsChoice::Sdf,
+    SettingsChoice::Ghost, SettingsChoice::Preview, SettingsChoice::WindowSize, SettingsChoice::Fullscreen,
+    SettingsChoice::Volume, SettingsChoice::Theme, SettingsChoice::Deadzone, SettingsChoice::RestartSeed,
+    SettingsChoice::Controls, SettingsChoice::Back];
+
+#[derive(Clone, Copy, PartialEq)]
+enum GameOverChoice {
+    NextPuzzle,
+    Retry,
+    RetrySameSeed,
+    Menu,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ControlsChoice {
+    Bind(Action),
+    //➔ the next action chosen loses its keys and buttons
+    Clear,
+    Reset,
+    Back,
+}
+
+fn frame_delay() {
+    thread::sleep(Duration::new(0, 1_000_000_000u32 / 60));
+}
+
+fn mode_label(setup: &GameSetup) -> String {
+    format!("MODE: {}", setup.mode.name())
+}
+
+fn ruleset_label(setup: &GameSetup) -> String {
+    format!("RULES: {}", setup.ruleset.name())
+}
+
+fn variant_label(setup: &GameSetup) -> String {
+    format!("VARIANT: {}", setup.variant.name())
+}
+
+fn level_label(setup: &GameSetup) -> String {
+    format!("START LEVEL: {}", setup.start_level)
+}
+
+fn title_menu(setup: &GameSetup) -> Menu<TitleChoice> {
+    let mut items = Vec::new();
+    if save::has_save() {
+        items.push(("CONTINUE".to_string(), TitleChoice::Continue));
+    }
+    items.push(("PLAY".to_string(), TitleChoice::Play));
+    items.push((format!("DAILY: {}", daily::challenge_text(daily::today())), TitleChoice::Daily));
+    items.push(("DAILY RESULTS".to_string(), TitleChoice::DailyResults));
+    items.push(("VERSUS".to_string(), TitleChoice::Versus));
+    items.push(("HOST VERSUS".to_string(), TitleChoice::HostVersus));
+    items.push(("JOIN VERSUS".to_string(), TitleChoice::JoinVersus));
+    items.push((mode_label(setup), TitleChoice::Mode));
+    for (index, label) in mode::option_labels(setup).into_iter().enumerate() {
+        items.push((label, TitleChoice::ModeOption(index)));
+    }
+    items.push((variant_label(setup), TitleChoice::Variant));
+    if setup.mode.picks_rules() {
+        items.push((ruleset_label(setup), TitleChoice::Ruleset));
+        items.push((level_label(setup), TitleChoice::StartLevel));
+    }
+    items.push(("SETTINGS".to_string(), TitleChoice::Settings));
+    items.push(("LEADERBOARDS".to_string(), TitleChoice::Leaderboards));
+    if Replay::exists() {
+        items.push(("WATCH REPLAY".to_string(), TitleChoice::Replay));
+    }
+    items.push(("QUIT".to_string(), TitleChoice::Quit));
+    Menu::new("TETRIS", items)
+}
+
+//➔ Shows the title screen until the player starts a game or leaves, `setup` keeps the choices made
+pub fn title_screen(graphics: &mut Graphics, event_pump: &mut sdl2::EventPump, gamepads: &mut Gamepads,
+    settings: &mut Settings, setup: &mut GameSetup) -> TitleAction {
+    let mut menu = title_menu(setup);
+    loop {
+        //➔ collected first, as some choices open other screens that need the event pump
+        let events = event_pump.poll_iter().collect::<Vec<_>>();
+        for event in events {
+            let input = match gamepad::as_menu_event(event) {
+                Event::Quit {..} => return TitleAction::Exit,
+                Event::KeyDown {keycode: Some(Keycode::Escape), ..} => return TitleAction::Exit,
+                Event::KeyDown {keycode: Some(keycode), ..} => menu.handle_key(keycode),
+                _ => None,
+            };
+            //➔ the rows holding a value change it like Right does, only PLAY starts the game
+            let input = match input {
+                Some(MenuInput::Choose(choice @ (TitleChoice::Mode | TitleChoice::ModeOption(_) | TitleChoice::Ruleset |
+                    TitleChoice::Variant | TitleChoice::StartLevel))) => Some(MenuInput::Adjust(choice, 1)),
+                input => input,
+            };
+            match input {
+                Some(MenuInput::Choose(TitleChoice::Continue)) => return TitleAction::Continue,
+                Some(MenuInput::Choose(TitleChoice::Daily)) => return TitleAction::Daily,
+                Some(MenuInput::Choose(TitleChoice::Versus)) => return TitleAction::Versus,
+                Some(MenuInput::Choose(TitleChoice::HostVersus)) => {
+                    let port = text_screen(graphics, event_pump, "HOST VERSUS", "PORT TO WAIT ON",
+                        &DEFAULT_PORT.to_string(), |c| c.is_ascii_digit(), |port| port.parse::<u16>().is_ok_and(|port| port > 0));
+                    match port {
+                        Some(Some(port)) => return TitleAction::Network(Role::Host(port.parse().unwrap_or(DEFAULT_PORT))),
+                        Some(None) => {},
+                        None => return TitleAction::Exit,
+                    }
+                },
+                Some(MenuInput::Choose(TitleChoice::JoinVersus)) => {
+                    //➔ names, IPv4 and IPv6 addresses, with a port after them
+                    let address = text_screen(graphics, event_pump, "JOIN VERSUS", "ADDRESS OF THE HOST", "127.0.0.1",
+                        |c| c.is_ascii_alphanumeric() || ".:-[]".contains(c), |address| !address.is_empty());
+                    match address {
+                        Some(Some(address)) => return TitleAction::Network(Role::Join(address)),
+                        Some(None) => {},
+                        None => return TitleAction::Exit,
+                    }
+                },
+                Some(MenuInput::Choose(TitleChoice::DailyResults)) => {
+                    let window_open = daily_results(graphics, event_pump);
+                    if !window_open {
+                        return TitleAction::Exit;
+                    }
+                },
+                Some(MenuInput::Choose(TitleChoice::Play)) => {
+                    //➔ puzzles and missions need files they can read
+                    if let Err(error) = setup.check_files() {
+                        println!("Cannot start the game: {}", error);
+                        continue;
+                    }
+                    return TitleAction::Play;
+                },
+                Some(MenuInput::Choose(TitleChoice::Settings)) => {
+                    let window_open = settings_screen(graphics, event_pump, gamepads, settings);
+                    if !window_open {
+                        return TitleAction::Exit;
+                    }
+                },
+                Some(MenuInput::Choose(TitleChoice::Leaderboards)) => {
+                    let window_open = leaderboards(graphics, event_pump, setup);
+                    if !window_open {
+                        return TitleAction::Exit;
+                    }
+                },
+                Some(MenuInput::Choose(TitleChoice::Replay)) => return TitleAction::WatchReplay,
+                Some(MenuInput::Choose(TitleChoice::Quit)) => return TitleAction::Exit,
+                Some(MenuInput::Adjust(TitleChoice::Mode, step)) => {
+                    setup.mode = setup.mode.cycle(step);
+                    //➔ rebuilt, as the modes do not all have the same options
+                    menu = title_menu(setup);
+                    menu.select(TitleChoice::Mode);
+                },
+                Some(MenuInput::Adjust(TitleChoice::ModeOption(index), step)) => {
+                    mode::adjust_option(setup, index, step);
+                    //➔ all of them, as picking another puzzle pack changes the puzzle count
+                    for (index, label) in mode::option_labels(setup).into_iter().enumerate() {
+                        menu.set_label(TitleChoice::ModeOption(index), label);
+                    }
+                },
+                Some(MenuInput::Adjust(TitleChoice::Ruleset, step)) => {
+                    setup.ruleset = setup.ruleset.cycle(step);
+                    //➔ the rulesets do not start from the same levels
+                    let (min, max) = setup.ruleset.start_levels();
+                    setup.start_level = setup.start_level.clamp(min, max);
+                    menu.set_label(TitleChoice::Ruleset, ruleset_label(setup));
+                    menu.set_label(TitleChoice::StartLevel, level_label(setup));
+                },
+                Some(MenuInput::Adjust(TitleChoice::Variant, step)) => {
+                    setup.variant = setup.variant.cycle(step);
+                    menu.set_label(TitleChoice::Variant, variant_label(setup));
+                },
+                Some(MenuInput::Adjust(TitleChoice::StartLevel, step)) => {
+                    let (min, max) = setup.ruleset.start_levels();
+                    setup.start_level = (setup.start_level as i32 + step).clamp(min as i32, max as i32) as u32;
+                    menu.set_label(TitleChoice::StartLevel, level_label(setup));
+                },
+                _ => {},
+            }
+        }
+
+        graphics.clear();
+        let center_x = graphics.width as i32 / 2;
+        menu.draw(&mut graphics.canvas, center_x, graphics.grid_y).expect("failed to render the title screen");
+        graphics.present();
+        frame_delay();
+    }
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value { "ON" } else { "OFF" }
+}
+
+fn settings_label(settings: &Settings, choice: SettingsChoice) -> String {
+    match choice {
+        SettingsChoice::Das => format!("DAS: {} MS", settings.das),
+        SettingsChoice::Arr => format!("ARR: {} MS", settings.arr),
+        SettingsChoice::Sdf => format!("SOFT DROP: {}X", settings.sdf),
+        SettingsChoice::Ghost => format!("GHOST: {}", on_off(settings.ghost)),
+        SettingsChoice::Preview => format!("PREVIEW: {}", settings.preview),
+        SettingsChoice::WindowSize => format!("WINDOW: {}X{}", settings.window_width, settings.window_height),
+        SettingsChoice::Fullscreen => format!("FULLSCREEN: {}", on_off(settings.fullscreen)),
+        SettingsChoice::Volume => format!("VOLUME: {}%", settings.volume),
+        SettingsChoice::Theme => format!("THEME: {}", settings.theme.name()),
+        SettingsChoice::Deadzone => format!("STICK DEADZONE: {}%", settings.deadzone),
+        SettingsChoice::RestartSeed => format!("RESTART PIECES: {}", if settings.restart_same_seed { "SAME" } else { "NEW" }),
+        SettingsChoice::Controls => "CONTROLS".to_string(),
+        SettingsChoice::Back => "BACK".to_string(),
+    }
+}
+
+fn step_value(value: u32, step: i32, (min, max): (u32, u32)) -> u32 {
+    (value as i32 + step).clamp(min as i32, max as i32) as u32
+}
+
+//➔ Changes a setting by one step, returns whether the display has to be updated
+fn adjust_setting(settings: &mut Settings, choice: SettingsChoice, step: i32) -> bool {
+    match choice {
+        SettingsChoice::Das => settings.das = step_value(settings.das, step * 10, settings::DAS_RANGE),
+        SettingsChoice::Arr => settings.arr = step_value(settings.arr, step * 10, settings::ARR_RANGE),
+        SettingsChoice::Sdf => settings.sdf = step_value(settings.sdf, step, settings::SDF_RANGE),
+        SettingsChoice::Ghost => settings.ghost = !settings.ghost,
+        SettingsChoice::RestartSeed => settings.restart_same_seed = !settings.restart_same_seed,
+        SettingsChoice::Preview => settings.preview = step_value(settings.preview, step, settings::PREVIEW_RANGE),
+        SettingsChoice::Volume => settings.volume = step_value(settings.volume, step * 10, settings::VOLUME_RANGE),
+        SettingsChoice::Deadzone => settings.deadzone = step_value(settings.deadzone, step * 5, settings::DEADZONE_RANGE),
+        SettingsChoice::WindowSize => {
+            let current = (settings.window_width, settings.window_height);
+            //➔ a size from the settings file that is not in the list starts from the default one
+            let index = WINDOW_SIZES.iter().position(|&size| size == current).unwrap_or(2) as i32;
+            let (width, height) = WINDOW_SIZES[(index + step).rem_euclid(WINDOW_SIZES.len() as i32) as usize];
+            settings.window_width = width;
+            settings.window_height = height;
+            return true;
+        },
+        SettingsChoice::Fullscreen => {
+            settings.fullscreen = !settings.fullscreen;
+            return true;
+        },
+        SettingsChoice::Theme => {
+            settings.theme = settings.theme.cycle(step);
+            return true;
+        },
+        SettingsChoice::Controls | SettingsChoice::Back => {},
+    }
+    false
+}
+
+fn save_settings(settings: &Settings) {
+    if let Err(e) = settings.save() {
+        println!("Could not save {}: {}", SETTINGS_FILE, e);
+    }
+}
+
+//➔ Lets the player change the settings, which are saved when leaving. Returns false if the window was closed
+pub fn settings_screen(graphics: &mut Graphics, event_pump: &mut sdl2::EventPump, gamepads: &mut Gamepads,
+    settings: &mut Settings) -> bool {
+    let items = SETTINGS_CHOICES.iter().map(|&choice| (settings_label(settings, choice), choice)).collect();
+    let mut menu = Menu::new("SETTINGS", items);
+    loop {
+        //➔ collected first, as the controls open their own screen that needs the event pump
+        let events = event_pump.poll_iter().collect::<Vec<_>>();
+        for event in events {
+            let input = match gamepad::as_menu_event(event) {
+                Event::Quit {..} => {
+                    save_settings(settings);
+                    return false;
+                },
+                Event::KeyDown {keycode: Some(Keycode::Escape), ..} => Some(MenuInput::Choose(SettingsChoice::Back)),
+                Event::KeyDown {keycode: Some(keycode), ..} => menu.handle_key(keycode),
+                _ => None,
+            };
+            let (choice, step) = match input {
+                Some(MenuInput::Choose(SettingsChoice::Back)) => {
+                    save_settings(settings);
+                    return true;
+                },
+                Some(MenuInput::Choose(SettingsChoice::Controls)) => {
+                    let window_open = controls_screen(graphics, event_pump, gamepads, settings);
+                    if !window_open {
+                        save_settings(settings);
+                        return false;
+                    }
+                    continue;
+                },
+                Some(MenuInput::Choose(choice)) => (choice, 1),
+                Some(MenuInput::Adjust(choice, step)) => (choice, step),
+                None => continue,
+            };
+            if adjust_setting(settings, choice, step) {
+                if let Err(e) = graphics.apply_settings(settings) {
+                    println!("Could not change the display: {}", e);
+                }
+            }
+            menu.set_label(choice, settings_label(settings, choice));
+        }
+
+        graphics.clear();
+        let center_x = graphics.width as i32 / 2;
+        menu.draw(&mut graphics.canvas, center_x, graphics.grid_y).expect("failed to render the settings");
+        graphics.present();
+        frame_delay();
+    }
+}
+
+fn controls_label(settings: &Settings, choice: ControlsChoice) -> String {
+    match choice {
+        ControlsChoice::Bind(action) => {
+            let keys = settings.keys(action).iter().map(|key| key.name()).collect::<Vec<_>>();
+            let buttons = settings.buttons(None, action).iter().map(|button| button.string()).collect::<Vec<_>>();
+            let keys = if keys.is_empty() { "NONE".to_string() } else { keys.join(", ") };
+            let buttons = if buttons.is_empty() { "NONE".to_string() } else { buttons.join(", ") };
+            format!("{}: {}  PAD: {}", action.label(), keys, buttons).to_uppercase()
+        },
+        ControlsChoice::Clear => "CLEAR BINDINGS".to_string(),
+        ControlsChoice::Reset => "RESET TO DEFAULTS".to_string(),
+        ControlsChoice::Back => "BACK".to_string(),
+    }
+}
+
+//➔ Binds the next key or controller button pressed to `action`, returns the message to show.
+//   `None` if the event was not a key or button press
+fn bind_input(event: &Event, gamepads: &Gamepads, settings: &mut Settings, action: Action) -> Option<String> {
+    let (name, previous) = match *event {
+        Event::KeyDown {keycode: Some(keycode), repeat: false, ..} => {
+            (keycode.name(), settings.bind_key(action, keycode))
+        },
+        Event::ControllerButtonDown {which, button, ..} => {
+            (button.string(), settings.bind_button(gamepads.guid(which), action, button))
+        },
+        _ => return None,
+    };
+    let message = match previous {
+        Some(previous) => format!("{} MOVED FROM {} TO {}", name, previous.label(), action.label()),
+        None => format!("{} BOUND TO {}", name, action.label()),
+    };
+    Some(message.to_uppercase())
+}
+
+//➔ Lets the player add keys and controller buttons to every action or clear them, returns false if the window was closed
+fn controls_screen(graphics: &mut Graphics, event_pump: &mut sdl2::EventPump, gamepads: &mut Gamepads,
+    settings: &mut Settings) -> bool {
+    let mut choices = Action::ALL.iter().map(|&action| ControlsChoice::Bind(action)).collect::<Vec<_>>();
+    choices.push(ControlsChoice::Clear);
+    choices.push(ControlsChoice::Reset);
+    choices.push(ControlsChoice::Back);
+    let items = choices.iter().map(|&choice| (controls_label(settings, choice), choice)).collect();
+    let mut menu = Menu::new("CONTROLS", items);
+    //➔ the action waiting for a key or button, if any
+    let mut binding: Option<Action> = None;
+    //➔ the next action chosen is cleared instead of getting a key or button
+    let mut clearing = false;
+    let mut message = String::new();
+    let white = Color::RGB(255, 255, 255);
+
+    loop {
+        for event in event_pump.poll_iter() {
+            if let Event::Quit {..} = event {
+                return false;
+            }
+            if let Event::ControllerDeviceAdded {..} | Event::ControllerDeviceRemoved {..} = event {
+                gamepads.refresh();
+            }
+            if let Some(action) = binding {
+                //➔ escape cancels, so it can only be bound through the settings file
+                if let Event::KeyDown {keycode: Some(Keycode::Escape), ..} = event {
+                    binding = None;
+                    message.clear();
+                } else if let Some(bound) = bind_input(&event, gamepads, settings, action) {
+                    binding = None;
+                    message = bound;
+                    for &choice in choices.iter() {
+                        menu.set_label(choice, controls_label(settings, choice));
+                    }
+                }
+                continue;
+            }
+            let input = match gamepad::as_menu_event(event) {
+                Event::KeyDown {keycode: Some(Keycode::Escape), ..} if clearing => {
+                    clearing = false;
+                    message.clear();
+                    None
+                },
+                Event::KeyDown {keycode: Some(Keycode::Escape), ..} => Some(MenuInput::Choose(ControlsChoice::Back)),
+                Event::KeyDown {keycode: Some(keycode), ..} => menu.handle_key(keycode),
+                _ => None,
+            };
+            match input {
+                Some(MenuInput::Choose(ControlsChoice::Bind(action))) if clearing => {
+                    settings.clear_bindings(action);
+                    clearing = false;
+                    message = format!("{} CLEARED", action.label());
+                    menu.set_label(ControlsChoice::Bind(action), controls_label(settings, ControlsChoice::Bind(action)));
+                },
+                Some(MenuInput::Choose(ControlsChoice::Bind(action))) => {
+                    binding = Some(action);
+                    message = format!("PRESS A KEY OR BUTTON TO ADD TO {}, ESCAPE CANCELS", action.label());
+                },
+                Some(MenuInput::Choose(ControlsChoice::Clear)) => {
+                    clearing = true;
+                    message = "CHOOSE THE ACTION TO CLEAR, ESCAPE CANCELS".to_string();
+                },
+                Some(MenuInput::Choose(ControlsChoice::Reset)) => {
+                    clearing = false;
+                    settings.reset_bindings();
+                    message = "CONTROLS RESET TO DEFAULTS".to_string();
+                    for &choice in choices.iter() {
+                        menu.set_label(choice, controls_label(settings, choice));
+                    }
+                },
+                Some(MenuInput::Choose(ControlsChoice::Back)) => return true,
+                _ => {},
+            }
+        }
+
+        graphics.clear();
+        let center_x = graphics.width as i32 / 2;
+        let y = graphics.grid_y - TETRIS_HEIGHT as i32 * 2;
+        menu.draw(&mut graphics.canvas, center_x, y).expect("failed to render the controls");
+        draw_text_centered(&mut graphics.canvas, &message, center_x,
+            graphics.height as i32 - TETRIS_HEIGHT as i32 * 2, TEXT_SCALE, white).expect("failed to render text");
+        graphics.present();
+        frame_delay();
+    }
+}
+
+//➔ Shows a title and some lines of text until a key is pressed, returns false if the window was closed
+pub fn message_screen(graphics: &mut Graphics, event_pump: &mut sdl2::EventPump, title: &str, lines: &[String]) -> bool {
+    let white = Color::RGB(255, 255, 255);
+    loop {
+        for event in event_pump.poll_iter() {
+            match gamepad::as_menu_event(event) {
+                Event::Quit {..} => return false,
+                Event::KeyDown {keycode: Some(Keycode::Escape), ..} |
+                Event::KeyDown {keycode: Some(Keycode::Return), ..} |
+                Event::KeyDown {keycode: Some(Keycode::KpEnter), ..} |
+                Event::KeyDown {keycode: Some(Keycode::Space), ..} => return true,
+                _ => {},
+            }
+        }
+
+        graphics.clear();
+        let center_x = graphics.width as i32 / 2;
+        let mut y = graphics.grid_y;
+        draw_text_centered(&mut graphics.canvas, title, center_x, y, TITLE_SCALE, white).expect("failed to render text");
+        y += text_height(TITLE_SCALE) as i32 * 2;
+        for line in lines {
+            draw_text_centered(&mut graphics.canvas, line, center_x, y, TEXT_SCALE, white).expect("failed to render text");
+            y += text_height(TEXT_SCALE) as i32 * 2;
+        }
+        draw_text_centered(&mut graphics.canvas, "PRESS ENTER", center_x,
+            graphics.height as i32 - TETRIS_HEIGHT as i32 * 2, TEXT_SCALE, white).expect("failed to render text");
+        graphics.present();
+        frame_delay();
+    }
+}
+
+//➔ Lets the player type a line starting from `text`, made of the characters `allowed` lets through,
+//   which can be entered once it is `valid`. Returns it, None inside if the player went back,
+//   or None if the window was closed
+fn text_screen(graphics: &mut Graphics, event_pump: &mut sdl2::EventPump, title: &str, prompt: &str, text: &str,
+    allowed: fn(char) -> bool, valid: fn(&str) -> bool) -> Option<Option<String>> {
+    let white = Color::RGB(255, 255, 255);
+    let mut line = text.to_string();
+    loop {
+        for event in event_pump.poll_iter() {
+            match event {
+                Event::Quit {..} => return None,
+                Event::KeyDown {keycode: Some(Keycode::Escape), ..} => return Some(None),
+                Event::KeyDown {keycode: Some(Keycode::Return), ..} |
+                Event::KeyDown {keycode: Some(Keycode::KpEnter), ..} if valid(&line) => {
+                    return Some(Some(line));
+                },
+                Event::KeyDown {keycode: Some(Keycode::Backspace), ..} => {
+                    line.pop();
+                },
+                Event::TextInput {text, ..} => line.extend(text.chars().filter(|&c| allowed(c))),
+                _ => {},
+            }
+        }
+
+        graphics.clear();
+        let center_x = graphics.width as i32 / 2;
+        let mut y = graphics.grid_y;
+        draw_text_centered(&mut graphics.canvas, title, center_x, y, TITLE_SCALE, white).expect("failed to render text");
+        y += text_height(TITLE_SCALE) as i32 * 2;
+        draw_text_centered(&mut graphics.canvas, prompt, center_x, y, TEXT_SCALE, white).expect("failed to render text");
+        y += text_height(TEXT_SCALE) as i32 * 2;
+        draw_text_centered(&mut graphics.canvas, &format!("{}_", line), center_x, y, TEXT_SCALE, white)
+            .expect("failed to render text");
+        draw_text_centered(&mut graphics.canvas, "ENTER TO GO ON, ESCAPE TO GO BACK", center_x,
+            graphics.height as i32 - TETRIS_HEIGHT as i32 * 2, TEXT_SCALE, white).expect("failed to render text");
+        graphics.present();
+        frame_delay();
+    }
+}
+
+//➔ Shows the leaderboard of the mode picked on the title screen, returns false if the window was closed
+fn leaderboards(graphics: &mut Graphics, event_pump: &mut sdl2::EventPump, setup: &GameSetup) -> bool {
+    let title = mode::leaderboard_title(setup);
+    let (board, lower_is_better) = match mode::leaderboard(setup) {
+        Some(leaderboard) => leaderboard,
+        None => return message_screen(graphics, event_pump, &title, &["THIS MODE IS NOT RANKED".to_string()]),
+    };
+    let records = records::load_board(&board, lower_is_better);
+    let mut lines = records.iter().enumerate()
+        .map(|(rank, record)| format!("{}. {}", rank + 1, mode::record_text(setup, record)))
+        .collect::<Vec<_>>();
+    if lines.is_empty() {
+        lines.push("NO GAMES PLAYED YET".to_string());
+    }
+    message_screen(graphics, event_pump, &title, &lines)
+}
+
+//➔ Shows the ranked result of the daily challenge of the last days, returns false if the window was closed
+fn daily_results(graphics: &mut Graphics, event_pump: &mut sdl2::EventPump) -> bool {
+    let today = daily::today();
+    let lines = (0..daily::RESULT_DAYS.min(today + 1)).map(|days_ago| {
+        let day = today - days_ago;
+        let setup = daily::daily_setup(day);
+        let result = match mode::personal_best(&setup) {
+            Some(record) => mode::record_text(&setup, &record),
+            None if daily::attempts(day) > 0 => "NOT FINISHED".to_string(),
+            None => "NOT PLAYED".to_string(),
+        };
+        //➔ without the year, which the screen has no room for
+        format!("{} {}: {}", &daily::date_text(day)[5..], daily::challenge_text(day), result)
+    }).collect::<Vec<_>>();
+    message_screen(graphics, event_pump, "DAILY RESULTS", &lines)
+}
+
+//➔ How a game went, as shown once it is over
+pub fn game_summary(tetris: &Tetris, elapsed: Duration) -> Vec<String> {
+    vec![
+        format!("SCORE: {}", tetris.score),
+        format!("LINES: {}", tetris.num_lines),
+        format!("LEVEL: {}", tetris.current_level),
+        format!("TIME: {}", format_time(elapsed)),
+        format!("PIECES: {}", tetris.pieces_placed()),
+    ]
+}
+
+//➔ How a game went, as the game over screen shows it
+pub struct GameOutcome {
+    pub title: &'static str,
+    pub lines: Vec<String>,
+    //➔ offers to go on with the next puzzle of the pack
+    pub next_puzzle: bool,
+}
+
+//➔ Shows how the game went and lets the player retry, with the restart key as a shortcut
+pub fn game_over(graphics: &mut Graphics, event_pump: &mut sdl2::EventPump, gamepads: &Gamepads, settings: &Settings,
+    setup: &GameSetup, tetris: &Tetris, outcome: &GameOutcome) -> GameEnd {
+    let mut items = Vec::new();
+    if outcome.next_puzzle {
+        items.push(("NEXT PUZZLE".to_string(), GameOverChoice::NextPuzzle));
+    }
+    items.push(("RETRY".to_string(), GameOverChoice::Retry));
+    items.push(("RETRY SAME PIECES".to_string(), GameOverChoice::RetrySameSeed));
+    items.push(("MENU".to_string(), GameOverChoice::Menu));
+    let item_count = items.len() as i32;
+    let mut menu = Menu::new(outcome.title, items);
+    let white = Color::RGB(255, 255, 255);
+    loop {
+        for event in event_pump.poll_iter() {
+            let restart = match event {
+                Event::KeyDown {keycode: Some(keycode), repeat: false, ..} => settings.action_for_key(keycode),
+                Event::ControllerButtonDown {which, button, ..} => settings.action_for_button(gamepads.guid(which), button),
+                _ => None,
+            } == Some(Action::Restart);
+            if restart {
+                return GameEnd::Retry(restart_seed(settings, setup, tetris));
+            }
+            let input = match gamepad::as_menu_event(event) {
+                Event::Quit {..} => return GameEnd::Exit,
+                Event::KeyDown {keycode: Some(Keycode::Escape), ..} => return GameEnd::Menu,
+                Event::KeyDown {keycode: Some(keycode), ..} => menu.handle_key(keycode),
+                _ => None,
+            };
+            match input {
+                Some(MenuInput::Choose(GameOverChoice::NextPuzzle)) => return GameEnd::NextPuzzle,
+                Some(MenuInput::Choose(GameOverChoice::Retry)) => return GameEnd::Retry(None),
+                Some(MenuInput::Choose(GameOverChoice::RetrySameSeed)) => return GameEnd::Retry(Some(tetris.seed)),
+                Some(MenuInput::Choose(GameOverChoice::Menu)) => return GameEnd::Menu,
+                _ => {},
+            }
+        }
+
+        graphics.clear();
+        let center_x = graphics.width as i32 / 2;
+        menu.draw(&mut graphics.canvas, center_x, graphics.grid_y).expect("failed to render the game over screen");
+        //➔ below the title and the items of the menu
+        let mut y = graphics.grid_y + text_height(TITLE_SCALE) as i32 * 2 + text_height(TEXT_SCALE) as i32 * (2 * item_count + 2);
+        for line in outcome.lines.iter() {
+            draw_text_centered(&mut graphics.canvas, line, center_x, y, TEXT_SCALE, white).expect("failed to render text");
+            y += text_height(TEXT_SCALE) as i32 * 2;
+        }
+        graphics.present();
+        frame_delay();
+    }
+}
@@ -0,0 +1,696 @@
+//➔ Player settings: keybindings, handling and display options.
+//
+// They live in a versioned text file next to the highscores, one `key value`
+// entry per line. Keybindings are written as `bind <action> <key>` using SDL key
+// names, with one line per key so an action can have several of them, and the
+// keys of the second player of a versus game as `bind2 <action> <key>`. Controller
+// buttons use SDL button names: `pad <action> <button>` applies to every
+// controller and `controller <guid> <action> <button>` to a single one. `none`
+// in place of the key or button leaves the action without any, so an action
+// whose keys all went to other actions stays that way. Bad entries are
+// reported and the default is kept for them.
+
+use crate::{read_from_file, write_into_file};
+
+use sdl2::controller::Button;
+use sdl2::keyboard::Keycode;
+use sdl2::pixels::Color;
+
+use std::io;
+
+pub const SETTINGS_FILE: &str = "settings.txt";
+const SETTINGS_VERSION: u32 = 1;
+
+pub const DAS_RANGE: (u32, u32) = (0, 1000);
+pub const ARR_RANGE: (u32, u32) = (0, 500);
+pub const SDF_RANGE: (u32, u32) = (1, 100);
+pub const PREVIEW_RANGE: (u32, u32) = (0, 5);
+pub const VOLUME_RANGE: (u32, u32) = (0, 100);
+pub const DEADZONE_RANGE: (u32, u32) = (5, 95);
+pub const WIDTH_RANGE: (u32, u32) = (1024, 7680);
+pub const HEIGHT_RANGE: (u32, u32) = (640, 4320);
+
+//➔ Something the player can do in a game, bound to one or more keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    SoftDrop,
+    HardDrop,
+    Rotate,
+    RotateCcw,
+    Rotate180,
+    Hold,
+    Pause,
+    Restart,
+    Undo,
+}
+
+impl Action {
+    pub const ALL: [Action; 11] = [Action::MoveLeft, Action::MoveRight, Action::SoftDrop, Action::HardDrop,
+                                   Action::Rotate, Action::RotateCcw, Action::Rotate180, Action::Hold,
+                                   Action::Pause, Action::Restart, Action::Undo];
+
+    //➔ Name used in the settings file
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::MoveLeft => "move_left",
+            Action::MoveRight => "move_right",
+            Action::SoftDrop => "soft_drop",
+            Action::HardDrop => "hard_drop",
+            Action::Rotate => "rotate",
+            Action::RotateCcw => "rotate_ccw",
+            Action::Rotate180 => "rotate_180",
+            Action::Hold => "hold",
+            Action::Pause => "pause",
+            Action::Restart => "restart",
+            Action::Undo => "undo",
+        }
+    }
+
+    //➔ Name shown on screen
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::MoveLeft => "MOVE LEFT",
+            Action::MoveRight => "MOVE RIGHT",
+            Action::SoftDrop => "SOFT DROP",
+            Action::HardDrop => "HARD DROP",
+            Action::Rotate => "ROTATE CW",
+            Action::RotateCcw => "ROTATE CCW",
+            Action::Rotate180 => "ROTATE 180",
+            Action::Hold => "HOLD",
+            Action::Pause => "PAUSE",
+            Action::Restart => "RESTART",
+            Action::Undo => "UNDO",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.iter().copied().find(|action| action.name() == name)
+    }
+
+    fn default_keys(&self) -> Vec<Keycode> {
+        match self {
+            Action::MoveLeft => vec![Keycode::Left],
+            Action::MoveRight => vec![Keycode::Right],
+            Action::SoftDrop => vec![Keycode::Down],
+            Action::HardDrop => vec![Keycode::Space],
+            Action::Rotate => vec![Keycode::Up],
+            Action::RotateCcw => vec![Keycode::Z],
+            Action::Rotate180 => vec![Keycode::A],
+            Action::Hold => vec![Keycode::C, Keycode::LShift],
+            Action::Pause => vec![Keycode::Escape, Keycode::P],
+            Action::Restart => vec![Keycode::R],
+            Action::Undo => vec![Keycode::Backspace],
+        }
+    }
+
+    //➔ Keys of the second player in versus, away from the default keys of the first one.
+    //   Pausing and restarting are left to the first player
+    fn default_versus_keys(&self) -> Vec<Keycode> {
+        match self {
+            Action::MoveLeft => vec![Keycode::J],
+            Action::MoveRight => vec![Keycode::L],
+            Action::SoftDrop => vec![Keycode::K],
+            Action::HardDrop => vec![Keycode::I],
+            Action::Rotate => vec![Keycode::O],
+            Action::RotateCcw => vec![Keycode::U],
+            Action::Rotate180 => vec![Keycode::Y],
+            Action::Hold => vec![Keycode::M],
+            Action::Pause | Action::Restart | Action::Undo => Vec::new(),
+        }
+    }
+
+    fn default_buttons(&self) -> Vec<Button> {
+        match self {
+            Action::MoveLeft => vec![Button::DPadLeft],
+            Action::MoveRight => vec![Button::DPadRight],
+            Action::SoftDrop => vec![Button::DPadDown],
+            Action::HardDrop => vec![Button::RightShoulder, Button::DPadUp],
+            Action::Rotate => vec![Button::A],
+            Action::RotateCcw => vec![Button::B],
+            Action::Rotate180 => vec![Button::Y],
+            Action::Hold => vec![Button::X, Button::LeftShoulder],
+            Action::Pause => vec![Button::Start],
+            Action::Restart => vec![Button::Back],
+            Action::Undo => vec![Button::RightStick],
+        }
+    }
+}
+
+type Bindings<T> = Vec<(Action, Vec<T>)>;
+
+//➔ The first binding read for an action replaces its defaults, the next ones add to them,
+//   no input only replaces the defaults. `rebound` remembers the actions already seen,
+//   under the scope of the binding
+fn add_binding<T: PartialEq>(bindings: &mut Bindings<T>, action: Action, input: Option<T>, scope: &str,
+    rebound: &mut Vec<String>) {
+    let seen = format!("{} {}", scope, action.name());
+    let index = match bindings.iter().position(|(bound, _)| *bound == action) {
+        Some(index) => index,
+        None => {
+            bindings.push((action, Vec::new()));
+            bindings.len() - 1
+        },
+    };
+    let inputs = &mut bindings[index].1;
+    if !rebound.contains(&seen) {
+        inputs.clear();
+        rebound.push(seen);
+    }
+    if let Some(input) = input.filter(|input| !inputs.contains(input)) {
+        inputs.push(input);
+    }
+}
+
+fn find_action<T: PartialEq>(bindings: &[(Action, Vec<T>)], input: &T) -> Option<Action> {
+    bindings.iter()
+        .find(|(_, inputs)| inputs.contains(input))
+        .map(|(action, _)| *action)
+}
+
+//➔ Adds `input` to the inputs of `action`, taking it away from any other action.
+//   Returns the action it was taken from
+fn move_binding<T: PartialEq + Copy>(bindings: &mut Bindings<T>, action: Action, input: T) -> Option<Action> {
+    let previous = find_action(bindings, &input).filter(|&bound| bound != action);
+    for (_, inputs) in bindings.iter_mut().filter(|(bound, _)| *bound != action) {
+        inputs.retain(|other| *other != input);
+    }
+    match bindings.iter_mut().find(|(bound, _)| *bound == action) {
+        Some((_, inputs)) if inputs.contains(&input) => {},
+        Some((_, inputs)) => inputs.push(input),
+        None => bindings.push((action, vec![input])),
+    }
+    previous
+}
+
+//➔ Leaves `action` without inputs
+fn clear_binding<T>(bindings: &mut Bindings<T>, action: Action) {
+    if let Some((_, inputs)) = bindings.iter_mut().find(|(bound, _)| *bound == action) {
+        inputs.clear();
+    }
+}
+
+//➔ One message for every input bound to more than one action, `what` describes the input
+fn find_conflicts<T: PartialEq>(bindings: &[(Action, Vec<T>)], what: impl Fn(&T) -> String) -> Vec<String> {
+    let mut conflicts = Vec::new();
+    for (index, (action, inputs)) in bindings.iter().enumerate() {
+        for input in inputs {
+            for (other, _) in bindings[index + 1..].iter().filter(|(_, others)| others.contains(input)) {
+                conflicts.push(format!("{} is bound to both `{}` and `{}`, only `{}` will work",
+                    what(input), action.name(), other.name(), action.name()));
+            }
+        }
+    }
+    conflicts
+}
+
+fn action_names() -> String {
+    Action::ALL.iter().map(|action| action.name()).collect::<Vec<_>>().join(", ")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Theme {
+    Classic,
+    Dark,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 2] = [Theme::Classic, Theme::Dark];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Theme::Classic => "classic",
+            Theme::Dark => "dark",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Theme> {
+        Theme::ALL.iter().copied().find(|theme| theme.name() == name)
+    }
+
+    pub fn cycle(self, step: i32) -> Theme {
+        let len = Theme::ALL.len() as i32;
+        let index = Theme::ALL.iter().position(|&theme| theme == self).unwrap_or(0) as i32;
+        Theme::ALL[(index + step).rem_euclid(len) as usize]
+    }
+
+    pub fn background(&self) -> Color {
+        match self {
+            Theme::Classic => Color::RGB(255, 0, 0),
+            Theme::Dark => Color::RGB(24, 24, 32),
+        }
+    }
+
+    pub fn grid(&self) -> Color {
+        match self {
+            Theme::Classic => Color::RGB(255, 255, 255),
+            Theme::Dark => Color::RGB(48, 48, 60),
+        }
+    }
+
+    pub fn border(&self) -> Color {
+        match self {
+            Theme::Classic => Color::RGB(0, 0, 0),
+            Theme::Dark => Color::RGB(120, 120, 140),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub bindings: Bindings<Keycode>,
+    //➔ keys of the second player in versus games
+    pub versus_bindings: Bindings<Keycode>,
+    //➔ buttons of every controller, unless it has its own entry in `controller_bindings`
+    pub pad_bindings: Bindings<Button>,
+    //➔ per controller GUID, the actions bound differently from `pad_bindings`
+    pub controller_bindings: Vec<(String, Bindings<Button>)>,
+    //➔ share of the analog stick travel that is ignored, in percent
+    pub deadzone: u32,
+    //➔ whether restarting deals the same pieces again
+    pub restart_same_seed: bool,
+    //➔ delayed auto shift and auto repeat rate, in milliseconds
+    pub das: u32,
+    pub arr: u32,
+    //➔ soft drop factor: how many times faster than gravity a soft drop falls
+    pub sdf: u32,
+    pub ghost: bool,
+    //➔ number of upcoming pieces shown, 0 hides the preview
+    pub preview: u32,
+    pub window_width: u32,
+    pub window_height: u32,
+    pub fullscreen: bool,
+    //➔ kept for the sound effects, in percent
+    pub volume: u32,
+    pub theme: Theme,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            bindings: Action::ALL.iter().map(|action| (*action, action.default_keys())).collect(),
+            versus_bindings: Action::ALL.iter().map(|action| (*action, action.default_versus_keys())).collect(),
+            pad_bindings: Action::ALL.iter().map(|action| (*action, action.default_buttons())).collect(),
+            controller_bindings: Vec::new(),
+            deadzone: 25,
+            restart_same_seed: false,
+            das: 167,
+            arr: 33,
+            sdf: 20,
+            ghost: true,
+            preview: 3,
+            window_width: 1360,
+            window_height: 768,
+            fullscreen: false,
+            volume: 80,
+            theme: Theme::Classic,
+        }
+    }
+}
+
+fn parse_number(key: &str, value: &str, (min, max): (u32, u32)) -> Result<u32, String> {
+    match value.parse::<u32>() {
+        Ok(number) if number >= min && number <= max => Ok(number),
+        Ok(number) => Err(format!("`{}` must be between {} and {}, got {}", key, min, max, number)),
+        Err(_) => Err(format!("`{}` must be a number between {} and {}, got `{}`", key, min, max, value)),
+    }
+}
+
+fn parse_switch(key: &str, value: &str) -> Result<bool, String> {
+    match value {
+        "on" | "true" | "yes" => Ok(true),
+        "off" | "false" | "no" => Ok(false),
+        _ => Err(format!("`{}` must be `on` or `off`, got `{}`", key, value)),
+    }
+}
+
+//➔ Splits `<action> <rest>` and looks the action up
+fn parse_action(value: &str) -> Result<(Action, &str), String> {
+    let (name, rest) = value.split_once(char::is_whitespace).unwrap_or((value, ""));
+    let action = Action::from_name(name)
+        .ok_or_else(|| format!("unknown action `{}`, expected one of: {}", name, action_names()))?;
+    Ok((action, rest.trim()))
+}
+
+//➔ Written in place of the key or button of an action left without any
+const NO_INPUT: &str = "none";
+
+fn parse_key_binding(value: &str) -> Result<(Action, Option<Keycode>), String> {
+    let (action, key) = parse_action(value)?;
+    if key.is_empty() {
+        return Err(format!("`{}` needs a key", action.name()));
+    }
+    if key == NO_INPUT {
+        return Ok((action, None));
+    }
+    let keycode = Keycode::from_name(key).ok_or_else(|| format!("unknown key `{}` for `{}`", key, action.name()))?;
+    Ok((action, Some(keycode)))
+}
+
+fn parse_button_binding(value: &str) -> Result<(Action, Option<Button>), String> {
+    let (action, button) = parse_action(value)?;
+    if button.is_empty() {
+        return Err(format!("`{}` needs a controller button", action.name()));
+    }
+    if button == NO_INPUT {
+        return Ok((action, None));
+    }
+    //➔ SDL button names: a, b, x, y, back, start, leftshoulder, dpup, ...
+    let button = Button::from_string(button).ok_or_else(|| format!("unknown button `{}` for `{}`", button, action.name()))?;
+    Ok((action, Some(button)))
+}
+
+//➔ The lines of `bindings` in the settings file, `prefix` being what comes before the action
+fn binding_lines<T>(content: &mut String, prefix: &str, bindings: &Bindings<T>, name: impl Fn(&T) -> String) {
+    for (action, inputs) in bindings.iter() {
+        if inputs.is_empty() {
+            content.push_str(&format!("{} {} {}\n", prefix, action.name(), NO_INPUT));
+        }
+        for input in inputs {
+            content.push_str(&format!("{} {} {}\n", prefix, action.name(), name(input)));
+        }
+    }
+}
+
+fn switch_name(value: bool) -> &'static str {
+    if value { "on" } else { "off" }
+}
+
+impl Settings {
+    pub fn keys(&self, action: Action) -> &[Keycode] {
+        self.bindings.iter()
+            .find(|(bound, _)| *bound == action)
+            .map_or(&[], |(_, keys)| keys.as_slice())
+    }
+
+    //➔ Buttons of the controller `guid` for `action`, its own ones if it has any
+    pub fn buttons(&self, guid: Option<&str>, action: Action) -> &[Button] {
+        let own = guid.and_then(|guid| self.controller_bindings.iter().find(|(id, _)| id == guid));
+        own.and_then(|(_, bindings)| bindings.iter().find(|(bound, _)| *bound == action))
+            .or_else(|| self.pad_bindings.iter().find(|(bound, _)| *bound == action))
+            .map_or(&[], |(_, buttons)| buttons.as_slice())
+    }
+
+    //➔ Adds `keycode` to the keys of `action`, returns the action that lost it
+    pub fn bind_key(&mut self, action: Action, keycode: Keycode) -> Option<Action> {
+        move_binding(&mut self.bindings, action, keycode)
+    }
+
+    //➔ Adds `button` to the buttons of `action`, on the controller `guid` alone if it has its own
+    //   bindings and on every controller otherwise. Returns the action that lost the button
+    pub fn bind_button(&mut self, guid: Option<&str>, action: Action, button: Button) -> Option<Action> {
+        let own = guid.and_then(|guid| self.controller_bindings.iter_mut().find(|(id, _)| id == guid));
+        match own {
+            Some((_, bindings)) => move_binding(bindings, action, button),
+            None => move_binding(&mut self.pad_bindings, action, button),
+        }
+    }
+
+    //➔ Takes every key of the first player and every controller button away from `action`
+    pub fn clear_bindings(&mut self, action: Action) {
+        clear_binding(&mut self.bindings, action);
+        clear_binding(&mut self.pad_bindings, action);
+        for (_, bindings) in self.controller_bindings.iter_mut() {
+            clear_binding(bindings, action);
+        }
+    }
+
+    pub fn reset_bindings(&mut self) {
+        let defaults = Settings::default();
+        self.bindings = defaults.bindings;
+        self.versus_bindings = defaults.versus_bindings;
+        self.pad_bindings = defaults.pad_bindings;
+        self.controller_bindings = defaults.controller_bindings;
+    }
+
+    //➔ Keys and buttons bound to several actions at once
+    pub fn conflicts(&self) -> Vec<String> {
+        let mut conflicts = find_conflicts(&self.bindings, |key| format!("key `{}`", key.name()));
+        conflicts.extend(find_conflicts(&self.versus_bindings, |key| format!("second player key `{}`", key.name())));
+        for (action, keys) in self.versus_bindings.iter() {
+            for key in keys.iter().filter(|key| self.action_for_key(**key).is_some()) {
+                conflicts.push(format!("key `{}` of the second player's `{}` is a key of the first player, who gets it",
+                    key.name(), action.name()));
+            }
+        }
+        conflicts.extend(find_conflicts(&self.pad_bindings, |button| format!("button `{}`", button.string())));
+        for (guid, bindings) in self.controller_bindings.iter() {
+            conflicts.extend(find_conflicts(bindings, |button| format!("button `{}` of controller {}", button.string(), guid)));
+        }
+        conflicts
+    }
+
+    pub fn action_for_key(&self, keycode: Keycode) -> Option<Action> {
+        find_action(&self.bindings, &keycode)
+    }
+
+    pub fn versus_action_for_key(&self, keycode: Keycode) -> Option<Action> {
+        find_action(&self.versus_bindings, &keycode)
+    }
+
+    //➔ `guid` identifies the controller the button belongs to, if it is known
+    pub fn action_for_button(&self, guid: Option<&str>, button: Button) -> Option<Action> {
+        let own = guid.and_then(|guid| self.controller_bindings.iter().find(|(id, _)| id == guid));
+        match own {
+            Some((_, bindings)) => find_action(bindings, &button).or_else(|| {
+                //➔ actions the controller binds itself no longer answer to the shared buttons
+                find_action(&self.pad_bindings, &button)
+                    .filter(|action| !bindings.iter().any(|(bound, _)| bound == action))
+            }),
+            None => find_action(&self.pad_bindings, &button),
+        }
+    }
+
+    //➔ Reads the settings file, writing the defaults if there is none yet.
+    //   Returns the settings along with a message for every entry that was rejected.
+    pub fn load() -> (Settings, Vec<String>) {
+        match read_from_file(SETTINGS_FILE) {
+            Ok(content) => Settings::parse(&content),
+            Err(_) => {
+                let settings = Settings::default();
+                let mut errors = Vec::new();
+                if let Err(e) = settings.save() {
+                    errors.push(format!("could not write {}: {}", SETTINGS_FILE, e));
+                }
+                (settings, errors)
+            },
+        }
+    }
+
+    pub fn parse(content: &str) -> (Settings, Vec<String>) {
+        let mut settings = Settings::default();
+        let mut errors = Vec::new();
+        //➔ actions whose defaults were already replaced by a binding line
+        let mut rebound: Vec<String> = Vec::new();
+
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = match line.split_once(char::is_whitespace) {
+                Some((key, value)) => (key, value.trim()),
+                None => (line, ""),
+            };
+            let result = match key {
+                "version" => match value.parse::<u32>() {
+                    Ok(version) if version <= SETTINGS_VERSION => Ok(()),
+                    Ok(version) => Err(format!("file version {} is newer than this game, unknown entries are ignored", version)),
+                    Err(_) => Err(format!("`version` must be a number, got `{}`", value)),
+                },
+                "das" => parse_number(key, value, DAS_RANGE).map(|das| settings.das = das),
+                "arr" => parse_number(key, value, ARR_RANGE).map(|arr| settings.arr = arr),
+                "sdf" => parse_number(key, value, SDF_RANGE).map(|sdf| settings.sdf = sdf),
+                "ghost" => parse_switch(key, value).map(|ghost| settings.ghost = ghost),
+                "preview" => parse_number(key, value, PREVIEW_RANGE).map(|preview| settings.preview = preview),
+                "fullscreen" => parse_switch(key, value).map(|fullscreen| settings.fullscreen = fullscreen),
+                "volume" => parse_number(key, value, VOLUME_RANGE).map(|volume| settings.volume = volume),
+                "restart_same_seed" => parse_switch(key, value).map(|same| settings.restart_same_seed = same),
+                "deadzone" => parse_number(key, value, DEADZONE_RANGE).map(|deadzone| settings.deadzone = deadzone),
+                "theme" => match Theme::from_name(value) {
+                    Some(theme) => {
+                        settings.theme = theme;
+                        Ok(())
+                    },
+                    None => Err(format!("unknown theme `{}`, expected one of: {}", value,
+                        Theme::ALL.iter().map(|theme| theme.name()).collect::<Vec<_>>().join(", "))),
+                },
+                "window" => {
+                    let mut size = value.split_whitespace();
+                    match (size.next(), size.next(), size.next()) {
+                        (Some(width), Some(height), None) => parse_number("window width", width, WIDTH_RANGE)
+                            .and_then(|width| parse_number("window height", height, HEIGHT_RANGE).map(|height| (width, height)))
+                            .map(|(width, height)| {
+                                settings.window_width = width;
+                                settings.window_height = height;
+                            }),
+                        _ => Err(format!("`window` expects a width and a height, got `{}`", value)),
+                    }
+                },
+                "bind" => parse_key_binding(value).map(|(action, keycode)| {
+                    add_binding(&mut settings.bindings, action, keycode, "bind", &mut rebound)
+                }),
+                "bind2" => parse_key_binding(value).map(|(action, keycode)| {
+                    add_binding(&mut settings.versus_bindings, action, keycode, "bind2", &mut rebound)
+                }),
+                "pad" => parse_button_binding(value).map(|(action, button)| {
+                    add_binding(&mut settings.pad_bindings, action, button, "pad", &mut rebound)
+                }),
+                "controller" => match value.split_once(char::is_whitespace) {
+                    Some((guid, binding)) => parse_button_binding(binding.trim()).map(|(action, button)| {
+                        settings.bind_controller_button(guid, action, button, &mut rebound)
+                    }),
+                    None => Err(format!("`controller` expects a GUID, an action and a button, got `{}`", value)),
+                },
+                _ => Err(format!("unknown setting `{}`", key)),
+            };
+            if let Err(message) = result {
+                errors.push(format!("{} line {}: {}", SETTINGS_FILE, index + 1, message));
+            }
+        }
+        for conflict in settings.conflicts() {
+            errors.push(format!("{}: {}", SETTINGS_FILE, conflict));
+        }
+        (settings, errors)
+    }
+
+    fn bind_controller_button(&mut self, guid: &str, action: Action, button: Option<Button>, rebound: &mut Vec<String>) {
+        let index = match self.controller_bindings.iter().position(|(id, _)| id == guid) {
+            Some(index) => index,
+            None => {
+                self.controller_bindings.push((guid.to_string(), Vec::new()));
+                self.controller_bindings.len() - 1
+            },
+        };
+        add_binding(&mut self.controller_bindings[index].1, action, button, guid, rebound);
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        write_into_file(&self.text(), SETTINGS_FILE)
+    }
+
+    //➔ The settings as the settings file writes them, read back by `parse`
+    pub fn text(&self) -> String {
+        let mut content = String::new();
+        content.push_str(&format!("version {}\n", SETTINGS_VERSION));
+        content.push_str(&format!("das {}\n", self.das));
+        content.push_str(&format!("arr {}\n", self.arr));
+        content.push_str(&format!("sdf {}\n", self.sdf));
+        content.push_str(&format!("ghost {}\n", switch_name(self.ghost)));
+        content.push_str(&format!("preview {}\n", self.preview));
+        content.push_str(&format!("window {} {}\n", self.window_width, self.window_height));
+        content.push_str(&format!("fullscreen {}\n", switch_name(self.fullscreen)));
+        content.push_str(&format!("volume {}\n", self.volume));
+        content.push_str(&format!("theme {}\n", self.theme.name()));
+        content.push_str(&format!("deadzone {}\n", self.deadzone));
+        content.push_str(&format!("restart_same_seed {}\n", switch_name(self.restart_same_seed)));
+        binding_lines(&mut content, "bind", &self.bindings, |key| key.name());
+        binding_lines(&mut content, "bind2", &self.versus_bindings, |key| key.name());
+        binding_lines(&mut content, "pad", &self.pad_bindings, |button| button.string());
+        for (guid, bindings) in self.controller_bindings.iter() {
+            binding_lines(&mut content, &format!("controller {}", guid), bindings, |button| button.string());
+        }
+        content
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn action_left_without_keys_stays_so_after_saving() {
+        let mut settings = Settings::default();
+        assert_eq!(settings.bind_key(Action::Rotate, Keycode::Space), Some(Action::HardDrop));
+        assert!(settings.keys(Action::HardDrop).is_empty());
+        let (loaded, errors) = Settings::parse(&settings.text());
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert!(loaded.keys(Action::HardDrop).is_empty());
+        assert!(loaded.keys(Action::Rotate).contains(&Keycode::Space));
+        assert_eq!(loaded.action_for_key(Keycode::Space), Some(Action::Rotate));
+    }
+
+    #[test]
+    fn binding_a_key_keeps_the_other_keys_of_the_action() {
+        let mut settings = Settings::default();
+        let hold = settings.keys(Action::Hold).to_vec();
+        assert!(hold.len() > 1);
+        assert_eq!(settings.bind_key(Action::Hold, Keycode::Space), Some(Action::HardDrop));
+        assert_eq!(settings.keys(Action::Hold), [hold.as_slice(), &[Keycode::Space]].concat().as_slice());
+        assert_eq!(settings.bind_key(Action::Hold, Keycode::Space), None);
+        assert_eq!(settings.keys(Action::Hold).len(), hold.len() + 1);
+    }
+
+    #[test]
+    fn cleared_action_has_no_keys_or_buttons() {
+        let mut settings = Settings::default();
+        settings.clear_bindings(Action::Hold);
+        assert!(settings.keys(Action::Hold).is_empty());
+        assert!(settings.buttons(None, Action::Hold).is_empty());
+        assert!(!settings.keys(Action::Rotate).is_empty());
+        let (loaded, errors) = Settings::parse(&settings.text());
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert!(loaded.keys(Action::Hold).is_empty());
+    }
+
+    #[test]
+    fn button_left_without_actions_stays_so_after_saving() {
+        let mut settings = Settings::default();
+        assert_eq!(settings.bind_button(None, Action::Hold, Button::A), Some(Action::Rotate));
+        let (loaded, errors) = Settings::parse(&settings.text());
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert!(loaded.buttons(None, Action::Rotate).is_empty());
+        assert_eq!(loaded.action_for_button(None, Button::A), Some(Action::Hold));
+    }
+
+    #[test]
+    fn settings_load_back_as_saved() {
+        let mut settings = Settings {
+            das: 100,
+            arr: 0,
+            ghost: false,
+            ..Settings::default()
+        };
+        settings.bind_key(Action::Hold, Keycode::X);
+        let (loaded, errors) = Settings::parse(&settings.text());
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(loaded.text(), settings.text());
+    }
+
+    //➔ The messages of parsing `content`, which must keep the defaults of the rejected entries
+    fn errors_of(content: &str) -> Vec<String> {
+        let (settings, errors) = Settings::parse(content);
+        assert_eq!(settings.das, Settings::default().das);
+        assert_eq!(settings.ghost, Settings::default().ghost);
+        errors
+    }
+
+    #[test]
+    fn out_of_range_numbers_are_reported() {
+        let errors = errors_of("das 5000\n");
+        assert_eq!(errors, vec![format!("{} line 1: `das` must be between 0 and 1000, got 5000", SETTINGS_FILE)]);
+        let errors = errors_of("\ndas fast\n");
+        assert_eq!(errors, vec![format!("{} line 2: `das` must be a number between 0 and 1000, got `fast`", SETTINGS_FILE)]);
+    }
+
+    #[test]
+    fn bad_entries_are_reported_by_line() {
+        let errors = errors_of("ghost maybe\nwindow 800\nbind jump Space\nbind hold\nspeed 3\nversion 99\n");
+        assert_eq!(errors.len(), 6, "{:?}", errors);
+        assert!(errors[0].ends_with("line 1: `ghost` must be `on` or `off`, got `maybe`"));
+        assert!(errors[1].ends_with("line 2: `window` expects a width and a height, got `800`"));
+        assert!(errors[2].contains("line 3: unknown action `jump`"));
+        assert!(errors[3].ends_with("line 4: `hold` needs a key"));
+        assert!(errors[4].ends_with("line 5: unknown setting `speed`"));
+        assert!(errors[5].contains("line 6: file version 99 is newer than this game"));
+    }
+
+    #[test]
+    fn conflicting_keys_are_reported() {
+        let (settings, errors) = Settings::parse("bind rotate Space\n");
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert!(errors[0].contains("key `Space` is bound to both `hard_drop` and `rotate`"));
+        assert_eq!(settings.action_for_key(Keycode::Space), Some(Action::HardDrop));
+    }
+}
@@ -0,0 +1,50 @@
+//➔ Variants a game of any mode can be played with.
+//
+// Big doubles every block, so the board holds half as many columns and rows.
+// Mirror flips the board left to right every few pieces. Upside down only
+// changes the drawing: the pieces come in at the bottom and fall upwards.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Variant {
+    Normal,
+    Big,
+    Mirror,
+    UpsideDown,
+}
+
+//➔ Pieces placed between two flips of a mirror game
+pub const MIRROR_PIECES: u32 = 10;
+
+impl Variant {
+    pub const ALL: [Variant; 4] = [Variant::Normal, Variant::Big, Variant::Mirror, Variant::UpsideDown];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Variant::Normal => "NORMAL",
+            Variant::Big => "BIG",
+            Variant::Mirror => "MIRROR",
+            Variant::UpsideDown => "UPSIDE DOWN",
+        }
+    }
+
+    //➔ Names in files have no spaces
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            Variant::Normal => "normal",
+            Variant::Big => "big",
+            Variant::Mirror => "mirror",
+            Variant::UpsideDown => "upside_down",
+        }
+    }
+
+    pub fn from_file_name(name: &str) -> Option<Variant> {
+        Variant::ALL.iter().copied().find(|variant| variant.file_name() == name)
+    }
+
+    //➔ The variant `step` entries further in `ALL`, wrapping around at both ends
+    pub fn cycle(self, step: i32) -> Variant {
+        let len = Variant::ALL.len() as i32;
+        let index = Variant::ALL.iter().position(|&variant| variant == self).unwrap_or(0) as i32;
+        Variant::ALL[(index + step).rem_euclid(len) as usize]
+    }
+}
@@ -0,0 +1,199 @@
+//➔ Local versus: two boards side by side on one machine, sending garbage to each other.
+//
+// Both players get the same pieces and play by the rules picked on the title
+// screen, each with their own keys and controller (see `VersusInput`). The
+// boards run on the same clock and trade the garbage of their clears after
+// every frame. The first player to top out loses; when both do in the same
+// frame the game is a draw. How much garbage a clear sends and how it rises
+// are the attack rules of the attack file.
+
+use crate::attack::{Attack, AttackRules};
+use crate::clock::GameClock;
+use crate::engine::Engine;
+use crate::game::{self, restart_seed, GameEnd, GameSetup, PauseChoice};
+use crate::gamepad::Gamepads;
+use crate::graphics::Graphics;
+use crate::handling::Handling;
+use crate::hud::{self, VERSUS_COLUMN};
+use crate::input::VersusInput;
+use crate::menu::Menu;
+use crate::mode::GameMode;
+use crate::screens::{self, GameOutcome};
+use crate::settings::{Action, Settings};
+use crate::variant::Variant;
+use crate::{save_lines_sent, TETRIS_HEIGHT};
+
+use sdl2::event::{Event, WindowEvent};
+
+use std::error::Error;
+use std::thread;
+use std::time::Duration;
+
+const PLAYERS: [&str; 2] = ["PLAYER 1", "PLAYER 2"];
+
+//➔ The two boards of a versus game played by `rules`, dealt from `seed` or from a random one
+pub fn start_versus(setup: &GameSetup, seed: Option<u64>, rules: &AttackRules) -> [Engine; 2] {
+    let seed = seed.unwrap_or_else(rand::random::<u64>);
+    //➔ a plain game of each board, its end is up to the garbage
+    let setup = GameSetup {
+        mode: GameMode::Marathon,
+        variant: Variant::Normal,
+        ..setup.clone()
+    };
+    [0, 1].map(|player| {
+        let mut engine = Engine::new(setup.new_game(seed), Duration::ZERO, Duration::ZERO);
+        //➔ flipped so the holes do not follow the same numbers as the pieces, and apart for each player
+        engine.attack = Some(Attack::new(rules.clone(), !seed ^ player));
+        engine
+    })
+}
+
+//➔ What each board sent reaches the other one, returns the lines sent by each board
+pub fn exchange_garbage(engines: &mut [Engine; 2]) -> [u32; 2] {
+    let sent = engines.each_mut().map(|engine| engine.attack.as_mut().map_or(0, |attack| attack.send()));
+    for (engine, &lines) in engines.iter_mut().zip(sent.iter().rev()) {
+        if let Some(ref mut attack) = engine.attack {
+            attack.receive(lines, engine.time);
+        }
+    }
+    sent
+}
+
+//➔ Where the left edge of the board of `player` goes, with its column on its right
+pub fn board_x(graphics: &Graphics, player: usize) -> i32 {
+    let half = graphics.width as i32 / 2;
+    let width = TETRIS_HEIGHT as i32 * 10 + 20 + VERSUS_COLUMN;
+    half * player as i32 + (half - width) / 2 + 10
+}
+
+//➔ Both boards, each with its column and the name of its player
+pub fn draw_versus(graphics: &mut Graphics, engines: &[Engine; 2], settings: &Settings, names: [&str; 2]) -> Result<(), String> {
+    for (player, engine) in engines.iter().enumerate() {
+        graphics.place_board(Some(board_x(graphics, player)));
+        game::draw_game(graphics, engine, settings, Variant::Normal)?;
+        let (pending, sent) = engine.attack.as_ref().map_or((0, 0), |attack| (attack.pending(), attack.lines_sent));
+        hud::draw_versus_hud(graphics, &engine.tetris, names[player], pending, sent, settings.preview)?;
+    }
+    Ok(())
+}
+
+//➔ Who won, and for each player the lines sent and whether that is a new best in the highscore file
+fn versus_outcome(engines: &[Engine; 2]) -> GameOutcome {
+    let title = match (engines[0].topped_out, engines[1].topped_out) {
+        (true, true) => "DRAW",
+        (true, false) => "PLAYER 2 WINS",
+        _ => "PLAYER 1 WINS",
+    };
+    let lines = engines.iter().enumerate().map(|(player, engine)| {
+        let sent = engine.attack.as_ref().map_or(0, |attack| attack.lines_sent);
+        let record = if save_lines_sent(sent) { " NEW RECORD" } else { "" };
+        format!("{}: SENT {} LINES {}{}", PLAYERS[player], sent, engine.tetris.num_lines, record)
+    }).collect();
+    GameOutcome {
+        title,
+        lines,
+        next_puzzle: false,
+    }
+}
+
+//➔ Plays a versus game until a player tops out or the players leave it
+pub fn run_versus(graphics: &mut Graphics, event_pump: &mut sdl2::EventPump, gamepads: &mut Gamepads,
+    settings: &mut Settings, setup: &GameSetup) -> Result<GameEnd, Box<dyn Error>> {
+    let end = play_versus(graphics, event_pump, gamepads, settings, setup);
+    graphics.place_board(None);
+    end
+}
+
+fn play_versus(graphics: &mut Graphics, event_pump: &mut sdl2::EventPump, gamepads: &mut Gamepads,
+    settings: &mut Settings, setup: &GameSetup) -> Result<GameEnd, Box<dyn Error>> {
+    let mut engines = start_versus(setup, setup.seed, &AttackRules::load());
+    let mut clock = GameClock::new();
+    let mut handling = Handling::from_settings(settings);
+    let mut input = VersusInput::default();
+    let mut paused: Option<Menu<PauseChoice>> = None;
+
+    loop {
+        if let Some(ref mut menu) = paused {
+            match game::handle_pause_events(menu, settings, event_pump) {
+                Some(PauseChoice::Resume) => {
+                    paused = None;
+                    clock.resume();
+                },
+                Some(PauseChoice::Restart) => {
+                    engines = start_versus(setup, restart_seed(settings, setup, &engines[0].tetris), &AttackRules::load());
+                    clock = GameClock::new();
+                    paused = None;
+                },
+                Some(PauseChoice::Settings) => {
+                    let window_open = screens::settings_screen(graphics, event_pump, gamepads, settings);
+                    if !window_open {
+                        return Ok(GameEnd::Exit);
+                    }
+                    handling = Handling::from_settings(settings);
+                },
+                Some(PauseChoice::SaveAndQuit) | Some(PauseChoice::Quit) => return Ok(GameEnd::Menu),
+                Some(PauseChoice::NoSave) | None => {},
+            }
+        } else {
+            let now = clock.now();
+            let mut pause = false;
+            for event in event_pump.poll_iter() {
+                match event {
+                    Event::Quit {..} => return Ok(GameEnd::Exit),
+                    Event::Window {win_event: WindowEvent::FocusLost, ..} => pause = true,
+                    Event::ControllerDeviceAdded {..} | Event::ControllerDeviceRemoved {..} => gamepads.refresh(),
+                    event => input.handle_event(&event, settings, gamepads, now),
+                }
+            }
+            let mut restart = false;
+            for (player, engine) in engines.iter_mut().enumerate() {
+                let mut actions = input.poll(player, now);
+                //➔ either player can pause or restart, the boards only get the actions before that
+                let request = actions.iter().position(|action| {
+                    action.pressed && (action.action == Action::Pause || action.action == Action::Restart)
+                });
+                if let Some(index) = request {
+                    restart |= actions[index].action == Action::Restart;
+                    pause |= actions[index].action == Action::Pause;
+                    actions.truncate(index);
+                }
+                engine.advance(&actions, now, &handling);
+            }
+            //➔ what a board sent this frame reaches the other one
+            exchange_garbage(&mut engines);
+
+            if engines.iter().any(|engine| engine.topped_out) {
+                let outcome = versus_outcome(&engines);
+                graphics.place_board(None);
+                return Ok(screens::game_over(graphics, event_pump, gamepads, settings, setup, &engines[0].tetris, &outcome));
+            }
+            if restart {
+                engines = start_versus(setup, restart_seed(settings, setup, &engines[0].tetris), &AttackRules::load());
+                clock = GameClock::new();
+            } else if pause {
+                clock.pause();
+                paused = Some(game::pause_menu(Some("VERSUS CANNOT BE SAVED")));
+                for engine in engines.iter_mut() {
+                    engine.release_all();
+                }
+            }
+        }
+
+        graphics.clear();
+        match paused {
+            //➔ Both boards stay hidden while paused, the menu goes in the middle
+            Some(ref menu) => {
+                for player in 0..2 {
+                    graphics.place_board(Some(board_x(graphics, player)));
+                    graphics.draw_board(false);
+                }
+                let center_x = graphics.width as i32 / 2;
+                let y = graphics.grid_y + TETRIS_HEIGHT as i32 * 2;
+                menu.draw(&mut graphics.canvas, center_x, y)?;
+            },
+            None => draw_versus(graphics, &engines, settings, PLAYERS)?,
+        }
+        graphics.present();
+        thread::sleep(Duration::new(0, 1_000_000_000u32 / 60));
+    }
+}
@@ -0,0 +1,51 @@
+//➔ Zen mode: stacking without pressure.
+//
+// Topping out clears the board instead of ending the game, the gravity is
+// fixed or off, and the last placements can be undone. The game is copied
+// every time a piece comes in, so undoing brings back the board, the queue and
+// the piece as they were before that piece was placed.
+
+use crate::Tetris;
+
+use std::collections::VecDeque;
+
+pub struct Zen {
+    //➔ level whose gravity the pieces fall with, 0 for no gravity at all
+    pub gravity: u32,
+    //➔ placements that can be undone at most
+    undo_limit: usize,
+    //➔ the game as each of the last pieces came in, the current piece last
+    snapshots: VecDeque<Tetris>,
+}
+
+impl Zen {
+    pub fn new(gravity: u32, undo_limit: u32) -> Zen {
+        Zen {
+            gravity,
+            undo_limit: undo_limit as usize,
+            snapshots: VecDeque::new(),
+        }
+    }
+
+    //➔ Keeps a copy of the game, to be called whenever a new piece came in
+    pub fn snapshot(&mut self, tetris: &Tetris) {
+        self.snapshots.push_back(tetris.clone());
+        while self.snapshots.len() > self.undo_limit + 1 {
+            self.snapshots.pop_front();
+        }
+    }
+
+    //➔ The game as it was when the previous piece came in, if there is one left to go back to
+    pub fn undo(&mut self) -> Option<Tetris> {
+        if self.snapshots.len() < 2 {
+            return None;
+        }
+        self.snapshots.pop_back();
+        self.snapshots.back().cloned()
+    }
+
+    //➔ How many placements can be undone right now
+    pub fn undos_left(&self) -> usize {
+        self.snapshots.len().saturating_sub(1)
+    }
+}